@@ -92,18 +92,8 @@ pub fn handler(reg: Regs, thr_init: ThrsInit) {
 }
 
 async fn setup_clktree(rcc: &Rcc<thr::Rcc>, pwr: &Pwr, flash: &Flash) -> ConfiguredClk<HClk> {
-    let hseclk = rcc.stabilize(consts::HSECLK).await;
-    let pll = rcc
-        .select(consts::PLLSRC_HSECLK, hseclk)
-        .stabilize(consts::PLL)
-        .await;
-    let hclk = rcc.configure(consts::HCLK);
-    let pclk1 = rcc.configure(consts::PCLK1);
-    let pclk2 = rcc.configure(consts::PCLK2);
-    pwr.enable_overdrive();
-    flash.set_latency(consts::HCLK.get_wait_states(VoltageRange::HighVoltage));
     swo::flush();
-    swo::update_prescaler(consts::HCLK.f() / log::baud_rate!() - 1);
-    rcc.select(consts::SYSCLK_PLL, pll.p());
-    hclk
+    let clocks = rcc.apply(consts::CLKTREE, pwr, flash).await;
+    swo::update_prescaler(clocks.hclk.f() / log::baud_rate!() - 1);
+    clocks.hclk
 }
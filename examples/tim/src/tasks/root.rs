@@ -35,19 +35,10 @@ pub fn handler(reg: Regs, thr_init: ThrsInit) {
     let pwr = Pwr::with_enabled_clock(periph_pwr!(reg));
     let flash = Flash::new(periph_flash!(reg));
 
-    let hseclk = rcc.stabilize(consts::HSECLK).root_wait();
-    let pll = rcc
-        .select(consts::PLLSRC_HSECLK, hseclk)
-        .stabilize(consts::PLL)
-        .root_wait();
-    let hclk = rcc.configure(consts::HCLK);
-    let pclk1 = rcc.configure(consts::PCLK1);
-    let pclk2 = rcc.configure(consts::PCLK2);
-    pwr.enable_overdrive();
-    flash.set_latency(consts::HCLK.get_wait_states(VoltageRange::HighVoltage));
     swo::flush();
-    swo::update_prescaler(consts::HCLK.f() / log::baud_rate!() - 1);
-    rcc.select(consts::SYSCLK_PLL, pll.p());
+    let clocks = rcc.apply(consts::CLKTREE, &pwr, &flash).root_wait();
+    swo::update_prescaler(clocks.hclk.f() / log::baud_rate!() - 1);
+    let timclk1 = clocks.timclk1;
 
     let sw = Stopwatch::start_new();
 
@@ -55,13 +46,13 @@ pub fn handler(reg: Regs, thr_init: ThrsInit) {
     let tim2_setup = GeneralTimSetup::new(
         periph_tim2!(reg),
         thr.tim2,
-        pclk1,
+        timclk1,
         TimFreq::Nominal(consts::TIM2_FREQ),
     );
     let tim4_setup = GeneralTimSetup::new(
         periph_tim4!(reg),
         thr.tim4,
-        pclk1,
+        timclk1,
         TimFreq::Nominal(consts::TIM2_FREQ),
     );
 
@@ -63,19 +63,10 @@ pub fn handler(reg: Regs, thr_init: ThrsInit) {
     let pwr = Pwr::with_enabled_clock(periph_pwr!(reg));
     let flash = Flash::new(periph_flash!(reg));
 
-    let hseclk = rcc.stabilize(consts::HSECLK).root_wait();
-    let pll = rcc
-        .select(consts::PLLSRC_HSECLK, hseclk)
-        .stabilize(consts::PLL)
-        .root_wait();
-    let hclk = rcc.configure(consts::HCLK);
-    let pclk1 = rcc.configure(consts::PCLK1);
-    let pclk2 = rcc.configure(consts::PCLK2);
-    pwr.enable_overdrive();
-    flash.set_latency(consts::HCLK.get_wait_states(VoltageRange::HighVoltage));
     swo::flush();
-    swo::update_prescaler(consts::HCLK.f() / log::baud_rate!() - 1);
-    rcc.select(consts::SYSCLK_PLL, pll.p());
+    let clocks = rcc.apply(consts::CLKTREE, &pwr, &flash).root_wait();
+    swo::update_prescaler(clocks.hclk.f() / log::baud_rate!() - 1);
+    let pclk1 = clocks.pclk1;
 
     // Initialize dma.
     let dma1 = DmaCfg::with_enabled_clock(periph_dma1!(reg));
@@ -0,0 +1,196 @@
+use crate::DmaStream;
+use alloc::boxed::Box;
+use core::mem;
+use drone_core::fib::TryFiberStreamRing;
+use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::periph::dma::ch::{traits::*, DmaChMap};
+
+/// A buffer in a double-buffer transfer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DmaBuf {
+    /// The buffer addressed by DMA_SxM0AR.
+    Buf0,
+    /// The buffer addressed by DMA_SxM1AR.
+    Buf1,
+}
+
+/// Double-buffer stream overflow, i.e. a ready buffer was not consumed before the stream capacity was exceeded.
+#[derive(Debug)]
+pub struct DmaBufOverflow;
+
+/// The buffer could not be swapped because it is targeted by the dma controller.
+/// Holds the buffer that was not swapped in.
+#[derive(Debug)]
+pub struct DmaBufBusy(pub Box<[u8]>);
+
+/// Continuous double-buffer transfer that owns both buffers.
+///
+/// The dma controller alternates between the two buffers,
+/// and the idle buffer can be swapped for a new one while the other buffer is being transferred.
+pub struct DmaDoubleBuf<'sess, DmaCh: DmaChMap, DmaInt: IntToken> {
+    stream: &'sess mut DmaStream<DmaCh, DmaInt>,
+    bufs: [Box<[u8]>; 2],
+}
+
+impl<DmaCh: DmaChMap, DmaInt: IntToken> DmaStream<DmaCh, DmaInt> {
+    /// Start a continuous double-buffer transfer alternating between `buf0` and `buf1`,
+    /// and return a handle that stops the stream when dropped.
+    pub fn start_double_buffer(
+        &mut self,
+        mut buf0: Box<[u8]>,
+        mut buf1: Box<[u8]>,
+    ) -> DmaDoubleBuf<'_, DmaCh, DmaInt> {
+        assert_eq!(buf0.len(), buf1.len());
+
+        self.dma_ccr.modify_reg(|r, v| {
+            r.minc().set(v); // memory address pointer is incremented after each data transfer
+            r.dbm().set(v); // double buffer mode
+            r.ct().clear(v); // start with the buffer in M0AR
+            r.teie().clear(v); // a blocked buffer swap sets TEIF, which is polled by `swap_buffer`
+        });
+        let items = self.items(buf0.as_ptr() as usize, buf0.len());
+        self.items(buf1.as_ptr() as usize, buf1.len());
+
+        // Set buffer memory addresses.
+        self.dma_cm0ar.store_reg(|r, v| {
+            r.m0a().write(v, buf0.as_mut_ptr() as u32);
+        });
+        self.dma_cm1ar.store_reg(|r, v| {
+            r.m1a().write(v, buf1.as_mut_ptr() as u32);
+        });
+
+        // Set number of data items to transfer in each buffer.
+        self.dma_cndtr.store_reg(|r, v| {
+            r.ndt().write(v, items as u32);
+        });
+
+        // Clear transfer completed, transfer error, and fifo error interrupt flags.
+        self.dma_ifcr_ctcif.set_bit();
+        self.dma_ifcr_cteif.set_bit();
+        self.dma_ifcr_cfeif.set_bit();
+
+        // Enable stream.
+        self.dma_ccr.modify_reg(|r, v| r.en().set(v));
+
+        DmaDoubleBuf {
+            stream: self,
+            bufs: [buf0, buf1],
+        }
+    }
+}
+
+impl<'sess, DmaCh: DmaChMap, DmaInt: IntToken> DmaDoubleBuf<'sess, DmaCh, DmaInt> {
+    /// Get the buffer that is currently targeted by the dma controller.
+    pub fn current_buffer(&self) -> DmaBuf {
+        if self.stream.dma_ccr.ct().read_bit() {
+            DmaBuf::Buf1
+        } else {
+            DmaBuf::Buf0
+        }
+    }
+
+    /// Replace the idle buffer `which` with `buf` and return the replaced buffer, e.g. to process the received data.
+    /// This should be done right after the buffer is reported ready,
+    /// as it can only be replaced while the dma controller is transferring the other buffer.
+    /// `buf` must have the same length as the buffer it replaces.
+    ///
+    /// If the dma controller switched to `which` while its address was written, the write is blocked
+    /// and the stream is stopped by the hardware. The stream is then restarted at the beginning of
+    /// the buffer that it was transferring, and `buf` is returned in the error.
+    pub fn swap_buffer(
+        &mut self,
+        which: DmaBuf,
+        mut buf: Box<[u8]>,
+    ) -> Result<Box<[u8]>, DmaBufBusy> {
+        assert_eq!(buf.len(), self.bufs[0].len());
+        self.stream.items(buf.as_ptr() as usize, buf.len());
+
+        // Writing the address of the buffer that is currently targeted is a transfer error.
+        if self.current_buffer() == which {
+            return Err(DmaBufBusy(buf));
+        }
+        let addr = buf.as_mut_ptr() as u32;
+        match which {
+            DmaBuf::Buf0 => self.stream.dma_cm0ar.store_reg(|r, v| {
+                r.m0a().write(v, addr);
+            }),
+            DmaBuf::Buf1 => self.stream.dma_cm1ar.store_reg(|r, v| {
+                r.m1a().write(v, addr);
+            }),
+        }
+
+        // The dma controller may have switched to the buffer in between,
+        // in which case the write was blocked and the stream disabled with TEIF set.
+        if self.stream.dma_isr_teif.read_bit() {
+            self.restart();
+            return Err(DmaBufBusy(buf));
+        }
+
+        let index = match which {
+            DmaBuf::Buf0 => 0,
+            DmaBuf::Buf1 => 1,
+        };
+        Ok(mem::replace(&mut self.bufs[index], buf))
+    }
+
+    /// Restart the stream after a blocked buffer swap.
+    fn restart(&mut self) {
+        let stream = &mut *self.stream;
+
+        // Wait until the EN bit in DMA_SxCR register is reset.
+        while stream.dma_ccr.en().read_bit() {}
+
+        // Transfer the current buffer from the beginning.
+        let buf = &self.bufs[0];
+        let items = stream.items(buf.as_ptr() as usize, buf.len());
+        stream.dma_cndtr.store_reg(|r, v| {
+            r.ndt().write(v, items as u32);
+        });
+
+        // Clear transfer error and fifo error interrupt flags.
+        stream.dma_ifcr_cteif.set_bit();
+        stream.dma_ifcr_cfeif.set_bit();
+
+        // Enable stream.
+        stream.dma_ccr.modify_reg(|r, v| r.en().set(v));
+    }
+
+    /// Creates a new fallible stream of ready buffers.
+    /// The transfer complete interrupt must be enabled in the stream setup.
+    pub fn ready_stream(&self, capacity: usize) -> TryFiberStreamRing<DmaBuf, DmaBufOverflow> {
+        let dma_ccr = self.stream.dma_ccr;
+        let dma_isr_tcif = self.stream.dma_isr_tcif;
+        let dma_ifcr_ctcif = self.stream.dma_ifcr_ctcif;
+        self.stream.dma_int.add_try_stream(
+            capacity,
+            |_| Err(DmaBufOverflow),
+            fib::new_fn(move || {
+                if dma_isr_tcif.read_bit() {
+                    // Clear transfer completed interrupt flag.
+                    dma_ifcr_ctcif.set_bit();
+                    // The current target (CT) has already toggled to the next buffer,
+                    // so the other buffer is the one that has just completed.
+                    if dma_ccr.ct().read_bit() {
+                        fib::Yielded(Some(DmaBuf::Buf0))
+                    } else {
+                        fib::Yielded(Some(DmaBuf::Buf1))
+                    }
+                } else {
+                    fib::Yielded(None)
+                }
+            }),
+        )
+    }
+}
+
+impl<DmaCh: DmaChMap, DmaInt: IntToken> Drop for DmaDoubleBuf<'_, DmaCh, DmaInt> {
+    /// Stop the stream and leave double buffer mode before the buffers are released.
+    fn drop(&mut self) {
+        self.stream.stop();
+        self.stream.dma_ccr.modify_reg(|r, v| {
+            r.dbm().clear(v);
+            r.teie().set(v);
+        });
+        self.stream.dma_ifcr_cteif.set_bit();
+    }
+}
@@ -1,4 +1,5 @@
 use self::config::*;
+use crate::DmaDataSize;
 use core::marker::PhantomData;
 use drone_cortexm::{reg::prelude::*, thr::prelude::*};
 use drone_stm32_map::periph::dma::{
@@ -56,6 +57,16 @@ pub mod config {
         pub dma_int: DmaInt,
         /// Dma channel priority level.
         pub dma_pl: DmaPrio,
+        /// Peripheral data size.
+        pub dma_psize: DmaDataSize,
+        /// Memory data size.
+        pub dma_msize: DmaDataSize,
+        /// Direct or fifo mode.
+        pub dma_fifo: DmaFifo,
+        /// Peripheral burst transfer configuration.
+        pub dma_pburst: DmaBurst,
+        /// Memory burst transfer configuration.
+        pub dma_mburst: DmaBurst,
     }
 
     pub enum DmaPrio {
@@ -65,8 +76,78 @@ pub mod config {
         VeryHigh,
     }
 
+    /// Dma fifo configuration.
+    #[derive(Copy, Clone, PartialEq)]
+    pub enum DmaFifo {
+        /// Direct mode, i.e. each data item is transferred immediately without using the fifo.
+        Direct,
+        /// Fifo mode with a threshold level.
+        Fifo(DmaFifoThreshold),
+    }
+
+    /// Dma fifo threshold level of the four word fifo.
+    #[derive(Copy, Clone, PartialEq)]
+    pub enum DmaFifoThreshold {
+        Quarter,
+        Half,
+        ThreeQuarters,
+        Full,
+    }
+
+    impl DmaFifoThreshold {
+        /// Get the number of bytes in the fifo at the threshold level.
+        pub fn bytes(&self) -> usize {
+            match self {
+                DmaFifoThreshold::Quarter => 4,
+                DmaFifoThreshold::Half => 8,
+                DmaFifoThreshold::ThreeQuarters => 12,
+                DmaFifoThreshold::Full => 16,
+            }
+        }
+
+        pub(crate) fn bits(&self) -> u32 {
+            match self {
+                DmaFifoThreshold::Quarter => 0b00,
+                DmaFifoThreshold::Half => 0b01,
+                DmaFifoThreshold::ThreeQuarters => 0b10,
+                DmaFifoThreshold::Full => 0b11,
+            }
+        }
+    }
+
+    /// Dma burst transfer configuration.
+    #[derive(Copy, Clone, PartialEq)]
+    pub enum DmaBurst {
+        Single,
+        Incr4,
+        Incr8,
+        Incr16,
+    }
+
+    impl DmaBurst {
+        /// Get the number of beats in a burst.
+        pub fn beats(&self) -> usize {
+            match self {
+                DmaBurst::Single => 1,
+                DmaBurst::Incr4 => 4,
+                DmaBurst::Incr8 => 8,
+                DmaBurst::Incr16 => 16,
+            }
+        }
+
+        pub(crate) fn bits(&self) -> u32 {
+            match self {
+                DmaBurst::Single => 0b00,
+                DmaBurst::Incr4 => 0b01,
+                DmaBurst::Incr8 => 0b10,
+                DmaBurst::Incr16 => 0b11,
+            }
+        }
+    }
+
     pub trait NewDmaChSetup<Dma: DmaMap, DmaCh: DmaChMap, DmaStCh, DmaInt: IntToken> {
-        /// Initialize a dma channel setup with medium priority level.
+        /// Initialize a dma channel setup with medium priority level
+        /// and byte-by-byte single transfers in direct mode.
         fn new(ch: DmaChPeriph<DmaCh>, int: DmaInt) -> DmaChSetup<Dma, DmaCh, DmaStCh, DmaInt>;
     }
 
@@ -101,6 +182,11 @@ pub mod config {
                         dma_ch: ch,
                         dma_int: int,
                         dma_pl: DmaPrio::Medium,
+                        dma_psize: DmaDataSize::Byte,
+                        dma_msize: DmaDataSize::Byte,
+                        dma_fifo: DmaFifo::Direct,
+                        dma_pburst: DmaBurst::Single,
+                        dma_mburst: DmaBurst::Single,
                     }
                 }
             }
@@ -251,6 +337,7 @@ impl<Dma: DmaMap> DmaCfg<Dma> {
     }
 
     /// Initialize a dma channel.
+    /// Panics if the data size, fifo, and burst configuration is not allowed by RM0090.
     pub fn ch<DmaCh: DmaChMap, StCh, DmaInt: IntToken>(
         &self,
         setup: DmaChSetup<Dma, DmaCh, StCh, DmaInt>,
@@ -259,8 +346,16 @@ impl<Dma: DmaMap> DmaCfg<Dma> {
             dma_ch,
             dma_int,
             dma_pl,
+            dma_psize,
+            dma_msize,
+            dma_fifo,
+            dma_pburst,
+            dma_mburst,
             ..
         } = setup;
+        assert!(is_transfer_cfg_allowed(
+            dma_psize, dma_msize, dma_fifo, dma_pburst, dma_mburst
+        ));
         let pl = match dma_pl {
             DmaPrio::Low => 0b00,
             DmaPrio::Medium => 0b01,
@@ -272,6 +367,37 @@ impl<Dma: DmaMap> DmaCfg<Dma> {
             dma_ch,
             dma_int,
             dma_pl: pl,
+            dma_psize,
+            dma_msize,
+            dma_fifo,
+            dma_pburst,
+            dma_mburst,
+        }
+    }
+}
+
+/// Get whether a data size, fifo, and burst configuration is allowed by RM0090.
+fn is_transfer_cfg_allowed(
+    psize: DmaDataSize,
+    msize: DmaDataSize,
+    fifo: DmaFifo,
+    pburst: DmaBurst,
+    mburst: DmaBurst,
+) -> bool {
+    match fifo {
+        DmaFifo::Direct => {
+            // The memory data size is forced to the peripheral data size,
+            // and bursts are not possible in direct mode.
+            msize == psize && pburst == DmaBurst::Single && mburst == DmaBurst::Single
+        }
+        DmaFifo::Fifo(threshold) => {
+            // A memory burst must fit within the threshold level, which must be a whole number of bursts (RM0090 table 48).
+            let mburst = mburst.beats() * msize.bytes();
+            // A peripheral burst must fit within the fifo.
+            let pburst = pburst.beats() * psize.bytes();
+            mburst <= threshold.bytes()
+                && threshold.bytes() % mburst == 0
+                && pburst <= DmaFifoThreshold::Full.bytes()
         }
     }
 }
@@ -285,4 +411,164 @@ pub struct DmaChCfg<DmaCh: DmaChMap, DmaStCh, DmaInt: IntToken> {
     pub dma_int: DmaInt,
     /// Dma priority level.
     pub dma_pl: u32,
+    /// Peripheral data size.
+    pub dma_psize: DmaDataSize,
+    /// Memory data size.
+    pub dma_msize: DmaDataSize,
+    /// Direct or fifo mode.
+    pub dma_fifo: DmaFifo,
+    /// Peripheral burst transfer configuration.
+    pub dma_pburst: DmaBurst,
+    /// Memory burst transfer configuration.
+    pub dma_mburst: DmaBurst,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLDS: [DmaFifoThreshold; 4] = [
+        DmaFifoThreshold::Quarter,
+        DmaFifoThreshold::Half,
+        DmaFifoThreshold::ThreeQuarters,
+        DmaFifoThreshold::Full,
+    ];
+
+    /// Get whether a memory burst is allowed at each of the four fifo thresholds.
+    fn mburst_row(msize: DmaDataSize, mburst: DmaBurst) -> [bool; 4] {
+        let mut row = [false; 4];
+        for (allowed, threshold) in row.iter_mut().zip(THRESHOLDS.iter()) {
+            *allowed = is_transfer_cfg_allowed(
+                DmaDataSize::Byte,
+                msize,
+                DmaFifo::Fifo(*threshold),
+                DmaBurst::Single,
+                mburst,
+            );
+        }
+        row
+    }
+
+    #[test]
+    fn fifo_threshold_byte_bursts() {
+        // RM0090 table 48, MSIZE = byte.
+        assert_eq!([true; 4], mburst_row(DmaDataSize::Byte, DmaBurst::Single));
+        assert_eq!([true; 4], mburst_row(DmaDataSize::Byte, DmaBurst::Incr4));
+        assert_eq!(
+            [false, true, false, true],
+            mburst_row(DmaDataSize::Byte, DmaBurst::Incr8)
+        );
+        assert_eq!(
+            [false, false, false, true],
+            mburst_row(DmaDataSize::Byte, DmaBurst::Incr16)
+        );
+    }
+
+    #[test]
+    fn fifo_threshold_half_word_bursts() {
+        // RM0090 table 48, MSIZE = half-word.
+        assert_eq!(
+            [true; 4],
+            mburst_row(DmaDataSize::HalfWord, DmaBurst::Single)
+        );
+        assert_eq!(
+            [false, true, false, true],
+            mburst_row(DmaDataSize::HalfWord, DmaBurst::Incr4)
+        );
+        assert_eq!(
+            [false, false, false, true],
+            mburst_row(DmaDataSize::HalfWord, DmaBurst::Incr8)
+        );
+        assert_eq!(
+            [false; 4],
+            mburst_row(DmaDataSize::HalfWord, DmaBurst::Incr16)
+        );
+    }
+
+    #[test]
+    fn fifo_threshold_word_bursts() {
+        // RM0090 table 48, MSIZE = word.
+        assert_eq!([true; 4], mburst_row(DmaDataSize::Word, DmaBurst::Single));
+        assert_eq!(
+            [false, false, false, true],
+            mburst_row(DmaDataSize::Word, DmaBurst::Incr4)
+        );
+        assert_eq!([false; 4], mburst_row(DmaDataSize::Word, DmaBurst::Incr8));
+        assert_eq!([false; 4], mburst_row(DmaDataSize::Word, DmaBurst::Incr16));
+    }
+
+    #[test]
+    fn fifo_peripheral_burst_fits_fifo() {
+        let fifo = DmaFifo::Fifo(DmaFifoThreshold::Full);
+        let single = DmaBurst::Single;
+        assert!(is_transfer_cfg_allowed(
+            DmaDataSize::Byte,
+            DmaDataSize::Byte,
+            fifo,
+            DmaBurst::Incr16,
+            single
+        ));
+        assert!(is_transfer_cfg_allowed(
+            DmaDataSize::Word,
+            DmaDataSize::Byte,
+            fifo,
+            DmaBurst::Incr4,
+            single
+        ));
+        assert!(!is_transfer_cfg_allowed(
+            DmaDataSize::HalfWord,
+            DmaDataSize::Byte,
+            fifo,
+            DmaBurst::Incr16,
+            single
+        ));
+        assert!(!is_transfer_cfg_allowed(
+            DmaDataSize::Word,
+            DmaDataSize::Byte,
+            fifo,
+            DmaBurst::Incr8,
+            single
+        ));
+    }
+
+    #[test]
+    fn direct_mode() {
+        let single = DmaBurst::Single;
+        let direct = DmaFifo::Direct;
+        assert!(is_transfer_cfg_allowed(
+            DmaDataSize::Byte,
+            DmaDataSize::Byte,
+            direct,
+            single,
+            single
+        ));
+        assert!(is_transfer_cfg_allowed(
+            DmaDataSize::Word,
+            DmaDataSize::Word,
+            direct,
+            single,
+            single
+        ));
+        assert!(!is_transfer_cfg_allowed(
+            DmaDataSize::Byte,
+            DmaDataSize::HalfWord,
+            direct,
+            single,
+            single
+        ));
+        assert!(!is_transfer_cfg_allowed(
+            DmaDataSize::Byte,
+            DmaDataSize::Byte,
+            direct,
+            DmaBurst::Incr4,
+            single
+        ));
+        assert!(!is_transfer_cfg_allowed(
+            DmaDataSize::Byte,
+            DmaDataSize::Byte,
+            direct,
+            single,
+            DmaBurst::Incr4
+        ));
+    }
 }
@@ -1,12 +1,23 @@
+#![feature(never_type)]
 #![feature(prelude_import)]
 #![cfg_attr(not(feature = "std"), no_std)]
 
+mod double_buf;
 mod drv;
+mod mem;
+mod ring;
+mod stream;
+
+extern crate alloc;
 
 pub use self::drv::{
     config, DmaCfg, DmaChCfg, DmaStCh0, DmaStCh1, DmaStCh2, DmaStCh3, DmaStCh4, DmaStCh5, DmaStCh6,
     DmaStCh7, DmaStChToken,
 };
+pub use self::double_buf::{DmaBuf, DmaBufBusy, DmaBufOverflow, DmaDoubleBuf};
+pub use self::mem::{Dma2ChMap, DmaMem};
+pub use self::ring::{DmaRing, DmaRingOverflow};
+pub use self::stream::{DmaDataSize, DmaDir, DmaStream, DmaStreamSetup};
 pub use drone_stm32_map::periph::dma::DmaMap;
 pub use drone_stm32_map::periph::dma::ch::DmaChMap;
 
@@ -0,0 +1,260 @@
+use crate::{stream::handle_dma_err, DmaChCfg, DmaDataSize};
+use core::{cmp::min, mem::size_of};
+use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::periph::dma::ch::{
+    traits::*, Dma2Ch0, Dma2Ch1, Dma2Ch2, Dma2Ch3, Dma2Ch4, Dma2Ch5, Dma2Ch6, Dma2Ch7, DmaChMap,
+    DmaChPeriph,
+};
+
+/// Number of beats in a memory-to-memory burst.
+const BURST_BEATS: usize = 4;
+
+/// Maximum number of data items in a single transfer, rounded down to a whole number of bursts.
+const NDT_MAX: usize = 0xFFFF / BURST_BEATS * BURST_BEATS;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A dma channel on DMA2, the only dma controller capable of memory-to-memory transfers.
+/// The trait is sealed and implemented for the DMA2 channels only.
+pub trait Dma2ChMap: DmaChMap + sealed::Sealed {}
+
+macro_rules! dma2_ch {
+    ($($ch:ident),*) => {
+        $(
+            impl sealed::Sealed for $ch {}
+            impl Dma2ChMap for $ch {}
+        )*
+    };
+}
+
+dma2_ch!(Dma2Ch0, Dma2Ch1, Dma2Ch2, Dma2Ch3, Dma2Ch4, Dma2Ch5, Dma2Ch6, Dma2Ch7);
+
+/// Memory-to-memory dma driver.
+pub struct DmaMem<DmaCh: Dma2ChMap, DmaInt: IntToken> {
+    dma_ccr: DmaCh::SDmaCcr,
+    dma_cfcr: DmaCh::SDmaCfcr,
+    dma_cm0ar: DmaCh::SDmaCm0Ar,
+    dma_cndtr: DmaCh::SDmaCndtr,
+    dma_cpar: DmaCh::SDmaCpar,
+    dma_ifcr_cdmeif: DmaCh::SDmaIfcrCdmeif,
+    dma_ifcr_cfeif: DmaCh::SDmaIfcrCfeif,
+    dma_ifcr_ctcif: DmaCh::CDmaIfcrCtcif,
+    dma_ifcr_cteif: DmaCh::SDmaIfcrCteif,
+    dma_isr_dmeif: DmaCh::CDmaIsrDmeif,
+    dma_isr_feif: DmaCh::CDmaIsrFeif,
+    dma_isr_tcif: DmaCh::CDmaIsrTcif,
+    dma_isr_teif: DmaCh::CDmaIsrTeif,
+    dma_int: DmaInt,
+    dma_pl: u32,
+}
+
+/// Disables the stream when dropped, e.g. when a transfer future is dropped before the transfer has completed,
+/// such that the dma controller no longer accesses the borrowed buffers.
+struct TransferGuard<'a, DmaCh: DmaChMap> {
+    dma_ccr: &'a DmaCh::SDmaCcr,
+}
+
+impl<DmaCh: DmaChMap> Drop for TransferGuard<'_, DmaCh> {
+    fn drop(&mut self) {
+        if self.dma_ccr.en().read_bit() {
+            self.dma_ccr.modify_reg(|r, v| r.en().clear(v));
+
+            // Wait until the EN bit in DMA_SxCR register is reset.
+            while self.dma_ccr.en().read_bit() {}
+        }
+    }
+}
+
+impl<DmaCh: Dma2ChMap, DmaInt: IntToken> DmaMem<DmaCh, DmaInt> {
+    /// Initialize a memory-to-memory dma driver from a configured DMA2 channel.
+    /// The stream channel, data sizes, fifo, and burst configuration of the channel setup are not used.
+    pub fn init<DmaStCh>(cfg: DmaChCfg<DmaCh, DmaStCh, DmaInt>) -> Self {
+        let DmaChCfg {
+            dma_ch,
+            dma_int,
+            dma_pl,
+            ..
+        } = cfg;
+        let DmaChPeriph {
+            dma_ccr,
+            dma_cfcr,
+            dma_cm0ar,
+            dma_cndtr,
+            dma_cpar,
+            dma_ifcr_cdmeif,
+            dma_ifcr_cfeif,
+            dma_ifcr_ctcif,
+            dma_ifcr_cteif,
+            dma_isr_dmeif,
+            dma_isr_feif,
+            dma_isr_tcif,
+            dma_isr_teif,
+            ..
+        } = dma_ch;
+        Self {
+            dma_ccr,
+            dma_cfcr,
+            dma_cm0ar,
+            dma_cndtr,
+            dma_cpar,
+            dma_ifcr_cdmeif,
+            dma_ifcr_cfeif,
+            dma_ifcr_ctcif: dma_ifcr_ctcif.into_copy(),
+            dma_ifcr_cteif,
+            dma_isr_dmeif: dma_isr_dmeif.into_copy(),
+            dma_isr_feif: dma_isr_feif.into_copy(),
+            dma_isr_tcif: dma_isr_tcif.into_copy(),
+            dma_isr_teif: dma_isr_teif.into_copy(),
+            dma_int,
+            dma_pl,
+        }
+    }
+
+    /// Copy `src` into `dst` using memory-to-memory transfers.
+    /// Word or half-word transfers are used if the buffers are sufficiently aligned.
+    /// The stream is disabled if the future is dropped before the copy has completed.
+    pub async fn copy<T: Copy>(&mut self, src: &[T], dst: &mut [T]) {
+        assert_eq!(src.len(), dst.len());
+        let src_addr = src.as_ptr() as usize;
+        let dst_addr = dst.as_mut_ptr() as usize;
+        let len = src.len() * size_of::<T>();
+        let size = if (src_addr | dst_addr | len) % 4 == 0 {
+            DmaDataSize::Word
+        } else if (src_addr | dst_addr | len) % 2 == 0 {
+            DmaDataSize::HalfWord
+        } else {
+            DmaDataSize::Byte
+        };
+        unsafe {
+            self.transfer(src_addr, true, dst_addr, len / size.bytes(), size)
+                .await;
+        }
+    }
+
+    /// Fill `dst` with `pattern` using memory-to-memory transfers from a fixed source address.
+    /// The pattern must be 8, 16, or 32 bits wide.
+    /// The stream is disabled if the future is dropped before the fill has completed.
+    pub async fn fill<T: Copy>(&mut self, pattern: &T, dst: &mut [T]) {
+        let size = match size_of::<T>() {
+            1 => DmaDataSize::Byte,
+            2 => DmaDataSize::HalfWord,
+            4 => DmaDataSize::Word,
+            _ => panic!("Unsupported pattern size"),
+        };
+        let src_addr = pattern as *const T as usize;
+        let dst_addr = dst.as_mut_ptr() as usize;
+        assert_eq!((src_addr | dst_addr) % size.bytes(), 0);
+        unsafe {
+            self.transfer(src_addr, false, dst_addr, dst.len(), size)
+                .await;
+        }
+    }
+
+    async unsafe fn transfer(
+        &mut self,
+        src: usize,
+        pinc: bool,
+        dst: usize,
+        items: usize,
+        size: DmaDataSize,
+    ) {
+        let mut offset = 0;
+        while offset < items {
+            let cnt = min(NDT_MAX, items - offset);
+            let src = if pinc {
+                src + offset * size.bytes()
+            } else {
+                src
+            };
+            let dst = dst + offset * size.bytes();
+            self.transfer_chunk(src, pinc, dst, cnt, size).await;
+            offset += cnt;
+        }
+    }
+
+    async unsafe fn transfer_chunk(
+        &mut self,
+        src: usize,
+        pinc: bool,
+        dst: usize,
+        cnt: usize,
+        size: DmaDataSize,
+    ) {
+        // Bursts must not cross a 1 Kbyte address boundary, which is guaranteed when they are aligned to the burst size.
+        let burst = BURST_BEATS * size.bytes();
+        let mburst = size != DmaDataSize::Byte && dst % burst == 0 && cnt % BURST_BEATS == 0;
+        let pburst = mburst && pinc && src % burst == 0;
+
+        // The peripheral port is the source and the memory port is the destination.
+        self.dma_cpar.store_reg(|r, v| {
+            r.pa().write(v, src as u32);
+        });
+        self.dma_cm0ar.store_reg(|r, v| {
+            r.m0a().write(v, dst as u32);
+        });
+        self.dma_cndtr.store_reg(|r, v| {
+            r.ndt().write(v, cnt as u32);
+        });
+
+        // Memory-to-memory transfers are not allowed in direct mode.
+        self.dma_cfcr.store_reg(|r, v| {
+            r.dmdis().set(v); // fifo mode
+            r.fth().write(v, 0b11); // full fifo threshold
+        });
+
+        // Clear any outstanding interrupt flags.
+        self.dma_ifcr_ctcif.set_bit();
+        self.dma_ifcr_cteif.set_bit();
+        self.dma_ifcr_cdmeif.set_bit();
+        self.dma_ifcr_cfeif.set_bit();
+
+        // Start listen for the transfer to complete.
+        let dma_ifcr_ctcif = self.dma_ifcr_ctcif;
+        let dma_isr_dmeif = self.dma_isr_dmeif;
+        let dma_isr_feif = self.dma_isr_feif;
+        let dma_isr_tcif = self.dma_isr_tcif;
+        let dma_isr_teif = self.dma_isr_teif;
+        let dma_tc = self.dma_int.add_future(fib::new_fn(move || {
+            // Load _entire_ interrupt status register.
+            let val = dma_isr_teif.load_val();
+            handle_dma_err::<DmaCh>(&val, dma_isr_dmeif, dma_isr_feif, Some(dma_isr_teif));
+            if dma_isr_tcif.read(&val) {
+                // Clear transfer completed interrupt flag.
+                dma_ifcr_ctcif.set_bit();
+                fib::Complete(())
+            } else {
+                fib::Yielded(())
+            }
+        }));
+
+        let pl = self.dma_pl;
+        self.dma_ccr.store_reg(|r, v| {
+            r.pl().write(v, pl); // priority level
+            if mburst {
+                r.mburst().write(v, 0b01); // incremental burst of 4 beats
+            }
+            if pburst {
+                r.pburst().write(v, 0b01); // incremental burst of 4 beats
+            }
+            r.msize().write(v, size.bits()); // memory data size
+            r.psize().write(v, size.bits()); // peripheral data size
+            r.minc().set(v); // memory address pointer is incremented after each data transfer
+            if pinc {
+                r.pinc().set(v); // peripheral address pointer is incremented after each data transfer
+            }
+            r.dir().write(v, 0b10); // memory-to-memory
+            r.tcie().set(v); // transfer complete interrupt enable
+            r.teie().set(v); // transfer error interrupt enable
+        });
+
+        // Enable stream, and disable it again if the transfer is dropped before it has completed.
+        self.dma_ccr.modify_reg(|r, v| r.en().set(v));
+        let _guard = TransferGuard::<DmaCh> {
+            dma_ccr: &self.dma_ccr,
+        };
+
+        dma_tc.await;
+    }
+}
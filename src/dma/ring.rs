@@ -0,0 +1,195 @@
+use crate::DmaStream;
+use alloc::{boxed::Box, sync::Arc};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::periph::dma::ch::{traits::*, DmaChMap};
+
+/// Circular dma overflow, i.e. unread data was overwritten by the dma controller.
+#[derive(Debug, PartialEq)]
+pub struct DmaRingOverflow;
+
+/// State shared with the interrupt fiber.
+struct DmaRingState {
+    /// The number of half buffers written by the dma controller, modulo `usize::MAX`.
+    halves: AtomicUsize,
+    stopped: AtomicBool,
+}
+
+/// Circular peripheral-to-memory dma consumer.
+///
+/// The half transfer and transfer complete interrupts are used to track the number of
+/// half buffers written by the dma controller, so that the write position and overflows are known exactly,
+/// provided that the dma interrupt is serviced within the time it takes to fill half the ring buffer.
+pub struct DmaRing<'sess, DmaCh: DmaChMap, DmaInt: IntToken> {
+    stream: &'sess mut DmaStream<DmaCh, DmaInt>,
+    ring_buf: Box<[u8]>,
+    state: Arc<DmaRingState>,
+    read: usize,
+    circ: bool,
+    tcie: bool,
+}
+
+impl<DmaCh: DmaChMap, DmaInt: IntToken> DmaStream<DmaCh, DmaInt> {
+    /// Start a circular transfer into `ring_buf` and return a consumer that stops the stream when dropped.
+    /// The length of `ring_buf` must be a multiple of two peripheral data items.
+    pub fn start_ring(&mut self, mut ring_buf: Box<[u8]>) -> DmaRing<'_, DmaCh, DmaInt> {
+        assert_eq!(ring_buf.len() % (2 * self.psize.bytes()), 0);
+        let state = Arc::new(DmaRingState {
+            halves: AtomicUsize::new(0),
+            stopped: AtomicBool::new(false),
+        });
+
+        let dma_isr_htif = self.dma_isr_htif;
+        let dma_ifcr_chtif = self.dma_ifcr_chtif;
+        let dma_isr_tcif = self.dma_isr_tcif;
+        let dma_ifcr_ctcif = self.dma_ifcr_ctcif;
+        let fib_state = state.clone();
+        self.dma_int.add_fn(move || {
+            if fib_state.stopped.load(Ordering::Acquire) {
+                return fib::Complete(());
+            }
+            if dma_isr_htif.read_bit() {
+                // Clear half transfer interrupt flag.
+                dma_ifcr_chtif.set_bit();
+                fib_state.halves.fetch_add(1, Ordering::AcqRel);
+            }
+            if dma_isr_tcif.read_bit() {
+                // Clear transfer completed interrupt flag.
+                dma_ifcr_ctcif.set_bit();
+                fib_state.halves.fetch_add(1, Ordering::AcqRel);
+            }
+            fib::Yielded(())
+        });
+
+        let circ = self.dma_ccr.circ().read_bit();
+        let tcie = self.dma_ccr.tcie().read_bit();
+        self.dma_ccr.modify_reg(|r, v| {
+            r.circ().set(v); // circular mode
+            r.htie().set(v); // half transfer interrupt enable
+            r.tcie().set(v); // transfer complete interrupt enable
+        });
+        dma_ifcr_chtif.set_bit();
+        unsafe {
+            self.start_rx(ring_buf.as_mut());
+        }
+
+        DmaRing {
+            stream: self,
+            ring_buf,
+            state,
+            read: 0,
+            circ,
+            tcie,
+        }
+    }
+}
+
+impl<'sess, DmaCh: DmaChMap, DmaInt: IntToken> DmaRing<'sess, DmaCh, DmaInt> {
+    /// Get the number of bytes written by the dma controller since the transfer was started, modulo `usize::MAX`.
+    fn written(&self) -> usize {
+        let len = self.ring_buf.len();
+        let half = len / 2;
+        loop {
+            let halves = self.state.halves.load(Ordering::Acquire);
+            let pos = len - self.stream.remaining() * self.stream.psize.bytes();
+            if self.state.halves.load(Ordering::Acquire) != halves {
+                // A half buffer completed in between, try again.
+                continue;
+            }
+            // The dma controller is writing in the half after the completed ones,
+            // or in the one after that if the last event is not serviced yet.
+            let start = halves.wrapping_mul(half);
+            let written = (halves / 2).wrapping_mul(len).wrapping_add(pos % len);
+            if written.wrapping_sub(start) > len {
+                // The position has wrapped to the beginning of the ring buffer.
+                return written.wrapping_add(len);
+            }
+            return written;
+        }
+    }
+
+    /// Get the number of unread bytes in the ring buffer,
+    /// or an error if unread bytes have been overwritten.
+    pub fn available(&self) -> Result<usize, DmaRingOverflow> {
+        let available = self.written().wrapping_sub(self.read);
+        if available > self.ring_buf.len() {
+            Err(DmaRingOverflow)
+        } else {
+            Ok(available)
+        }
+    }
+
+    /// Get the contiguous readable slice at the read position without copying.
+    /// The slice does not contain all available bytes if they wrap around the end of the ring buffer.
+    /// Read bytes are released by `consume()`, which also reports whether they were overwritten while being read.
+    pub fn readable(&self) -> Result<&[u8], DmaRingOverflow> {
+        let available = self.available()?;
+        let first = self.read % self.ring_buf.len();
+        let cnt = available.min(self.ring_buf.len() - first);
+        Ok(&self.ring_buf[first..first + cnt])
+    }
+
+    /// Get the contiguous readable slice at the read position,
+    /// and wait for the next half transfer or transfer complete event if no bytes are available.
+    pub async fn read(&mut self) -> Result<&[u8], DmaRingOverflow> {
+        while self.available()? == 0 {
+            self.next_half().await;
+        }
+        self.readable()
+    }
+
+    /// Release `cnt` read bytes.
+    /// Returns an error if the bytes were overwritten while they were being read.
+    pub fn consume(&mut self, cnt: usize) -> Result<(), DmaRingOverflow> {
+        let available = self.available();
+        if let Ok(available) = available {
+            assert!(cnt <= available);
+        }
+        self.read = self.read.wrapping_add(cnt);
+        available.map(|_| ())
+    }
+
+    /// Skip all available bytes, e.g. to recover from an overflow.
+    pub fn clear(&mut self) {
+        self.read = self.written();
+    }
+
+    async fn next_half(&self) {
+        let dma_isr_htif = self.stream.dma_isr_htif;
+        let dma_isr_tcif = self.stream.dma_isr_tcif;
+        let state = self.state.clone();
+        let halves = state.halves.load(Ordering::Acquire);
+        let half = self.stream.dma_int.add_future(fib::new_fn(move || {
+            // The event is either pending or already counted, depending on the order of the fibers.
+            if dma_isr_htif.read_bit()
+                || dma_isr_tcif.read_bit()
+                || state.halves.load(Ordering::Acquire) != halves
+            {
+                fib::Complete(())
+            } else {
+                fib::Yielded(())
+            }
+        }));
+        if self.available() == Ok(0) {
+            half.await;
+        }
+    }
+}
+
+impl<DmaCh: DmaChMap, DmaInt: IntToken> Drop for DmaRing<'_, DmaCh, DmaInt> {
+    /// Stop the stream and restore its setup.
+    fn drop(&mut self) {
+        self.stream.stop();
+        let (circ, tcie) = (self.circ, self.tcie);
+        self.stream.dma_ccr.modify_reg(|r, v| {
+            if !circ {
+                r.circ().clear(v);
+            }
+            if !tcie {
+                r.tcie().clear(v);
+            }
+            r.htie().clear(v);
+        });
+        self.state.stopped.store(true, Ordering::Release);
+    }
+}
@@ -0,0 +1,527 @@
+use crate::{
+    config::{DmaBurst, DmaFifo},
+    DmaChCfg, DmaStChToken,
+};
+use core::ptr::addr_of_mut;
+use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::periph::dma::ch::{traits::*, DmaChMap, DmaChPeriph};
+use fib::FiberFuture;
+
+/// Word read from or written to by dummy transfers.
+static mut DUMMY: u32 = 0;
+
+/// Dma transfer direction.
+#[derive(Copy, Clone, PartialEq)]
+pub enum DmaDir {
+    PeriphToMem,
+    MemToPeriph,
+}
+
+/// Dma data size.
+#[derive(Copy, Clone, PartialEq)]
+pub enum DmaDataSize {
+    /// 8-bit.
+    Byte,
+    /// 16-bit.
+    HalfWord,
+    /// 32-bit.
+    Word,
+}
+
+impl DmaDataSize {
+    /// Get the number of bytes in a data item.
+    pub fn bytes(&self) -> usize {
+        match self {
+            DmaDataSize::Byte => 1,
+            DmaDataSize::HalfWord => 2,
+            DmaDataSize::Word => 4,
+        }
+    }
+
+    pub(crate) fn bits(&self) -> u32 {
+        match self {
+            DmaDataSize::Byte => 0b00,
+            DmaDataSize::HalfWord => 0b01,
+            DmaDataSize::Word => 0b10,
+        }
+    }
+}
+
+/// Dma stream transfer setup.
+#[derive(Copy, Clone)]
+pub struct DmaStreamSetup {
+    /// Transfer direction.
+    pub dir: DmaDir,
+    /// Peripheral data register address.
+    pub per_addr: u32,
+    /// Increment the peripheral address after each data transfer.
+    pub pinc: bool,
+    /// Increment the memory address after each data transfer.
+    pub minc: bool,
+    /// Circular mode, i.e. the number of data items is reloaded when the transfer completes.
+    pub circ: bool,
+    /// Enable the transfer complete interrupt, required by `transfer_complete()`.
+    pub tcie: bool,
+}
+
+impl DmaStreamSetup {
+    /// Initialize a stream setup with incrementing memory address
+    /// and fixed peripheral address in normal mode.
+    pub fn new(dir: DmaDir, per_addr: u32) -> Self {
+        Self {
+            dir,
+            per_addr,
+            pinc: false,
+            minc: true,
+            circ: false,
+            tcie: true,
+        }
+    }
+}
+
+/// Dma stream driver.
+pub struct DmaStream<DmaCh: DmaChMap, DmaInt: IntToken> {
+    pub(crate) dma_ccr: DmaCh::CDmaCcr,
+    pub(crate) dma_cfcr: DmaCh::SDmaCfcr,
+    pub(crate) dma_cm0ar: DmaCh::SDmaCm0Ar,
+    pub(crate) dma_cm1ar: DmaCh::SDmaCm1Ar,
+    pub(crate) dma_cndtr: DmaCh::CDmaCndtr,
+    pub(crate) dma_cpar: DmaCh::SDmaCpar,
+    pub(crate) dma_ifcr_cfeif: DmaCh::SDmaIfcrCfeif,
+    pub(crate) dma_ifcr_chtif: DmaCh::CDmaIfcrChtif,
+    pub(crate) dma_ifcr_ctcif: DmaCh::CDmaIfcrCtcif,
+    pub(crate) dma_ifcr_cteif: DmaCh::SDmaIfcrCteif,
+    pub(crate) dma_isr_dmeif: DmaCh::CDmaIsrDmeif,
+    pub(crate) dma_isr_feif: DmaCh::CDmaIsrFeif,
+    pub(crate) dma_isr_htif: DmaCh::CDmaIsrHtif,
+    pub(crate) dma_isr_tcif: DmaCh::CDmaIsrTcif,
+    pub(crate) dma_isr_teif: DmaCh::CDmaIsrTeif,
+    pub(crate) dma_int: DmaInt,
+    pub(crate) minc: bool,
+    pub(crate) psize: DmaDataSize,
+    pub(crate) msize: DmaDataSize,
+    pub(crate) fifo: DmaFifo,
+    pub(crate) mburst: DmaBurst,
+}
+
+impl<DmaCh: DmaChMap, DmaInt: IntToken> DmaStream<DmaCh, DmaInt> {
+    /// Initialize a dma stream from a configured dma channel,
+    /// using its data sizes, fifo, and burst configuration.
+    /// The stream panics on transfer, direct mode, and fifo errors.
+    pub fn init<DmaStCh: DmaStChToken>(
+        cfg: DmaChCfg<DmaCh, DmaStCh, DmaInt>,
+        setup: DmaStreamSetup,
+    ) -> Self {
+        let DmaChCfg {
+            dma_ch,
+            dma_int,
+            dma_pl,
+            dma_psize,
+            dma_msize,
+            dma_fifo,
+            dma_pburst,
+            dma_mburst,
+            ..
+        } = cfg;
+        let DmaChPeriph {
+            dma_ccr,
+            dma_cfcr,
+            dma_cm0ar,
+            dma_cm1ar,
+            dma_cndtr,
+            dma_cpar,
+            dma_ifcr_cfeif,
+            dma_ifcr_chtif,
+            dma_ifcr_ctcif,
+            dma_ifcr_cteif,
+            dma_isr_dmeif,
+            dma_isr_feif,
+            dma_isr_htif,
+            dma_isr_tcif,
+            dma_isr_teif,
+            ..
+        } = dma_ch;
+        let stream = Self {
+            dma_ccr: dma_ccr.into_copy(),
+            dma_cfcr,
+            dma_cm0ar,
+            dma_cm1ar,
+            dma_cndtr: dma_cndtr.into_copy(),
+            dma_cpar,
+            dma_ifcr_cfeif,
+            dma_ifcr_chtif: dma_ifcr_chtif.into_copy(),
+            dma_ifcr_ctcif: dma_ifcr_ctcif.into_copy(),
+            dma_ifcr_cteif,
+            dma_isr_dmeif: dma_isr_dmeif.into_copy(),
+            dma_isr_feif: dma_isr_feif.into_copy(),
+            dma_isr_htif: dma_isr_htif.into_copy(),
+            dma_isr_tcif: dma_isr_tcif.into_copy(),
+            dma_isr_teif: dma_isr_teif.into_copy(),
+            dma_int,
+            minc: setup.minc,
+            psize: dma_psize,
+            msize: dma_msize,
+            fifo: dma_fifo,
+            mburst: dma_mburst,
+        };
+        stream.init_stream(DmaStCh::NUM, dma_pl, &setup);
+        stream.init_transfers(dma_psize, dma_msize, dma_fifo, dma_pburst, dma_mburst);
+        stream.panic_on_err();
+        stream
+    }
+
+    fn init_stream(&self, chsel: u32, priority: u32, setup: &DmaStreamSetup) {
+        self.dma_cpar.store_reg(|r, v| {
+            r.pa().write(v, setup.per_addr); // peripheral address
+        });
+        self.dma_ccr.store_reg(|r, v| {
+            r.chsel().write(v, chsel); // channel selection
+            r.pl().write(v, priority); // priority level
+            if setup.minc {
+                r.minc().set(v); // memory address pointer is incremented after each data transfer
+            }
+            if setup.pinc {
+                r.pinc().set(v); // peripheral address pointer is incremented after each data transfer
+            }
+            r.dir().write(
+                v,
+                match setup.dir {
+                    DmaDir::PeriphToMem => 0b00,
+                    DmaDir::MemToPeriph => 0b01,
+                },
+            );
+            if setup.circ {
+                r.circ().set(v); // circular mode
+            }
+            if setup.tcie {
+                r.tcie().set(v); // transfer complete interrupt enable
+            }
+            r.teie().set(v); // transfer error interrupt enable
+        });
+    }
+
+    fn init_transfers(
+        &self,
+        psize: DmaDataSize,
+        msize: DmaDataSize,
+        fifo: DmaFifo,
+        pburst: DmaBurst,
+        mburst: DmaBurst,
+    ) {
+        self.dma_cfcr.store_reg(|r, v| {
+            if let DmaFifo::Fifo(threshold) = fifo {
+                r.dmdis().set(v); // direct mode disable, i.e. fifo mode
+                r.fth().write(v, threshold.bits()); // fifo threshold
+            }
+        });
+        self.dma_ccr.modify_reg(|r, v| {
+            r.msize().write(v, msize.bits()); // memory data size
+            r.psize().write(v, psize.bits()); // peripheral data size
+            r.mburst().write(v, mburst.bits()); // memory burst transfer configuration
+            r.pburst().write(v, pburst.bits()); // peripheral burst transfer configuration
+        });
+    }
+
+    fn panic_on_err(&self) {
+        // Attach dma error handler
+        let dma_ccr = self.dma_ccr;
+        let dma_isr_dmeif = self.dma_isr_dmeif;
+        let dma_isr_feif = self.dma_isr_feif;
+        let dma_isr_teif = self.dma_isr_teif;
+        self.dma_int.add_fn(move || {
+            // Load _entire_ interrupt status register.
+            // The value is not masked to TEIF.
+            let val = dma_isr_teif.load_val();
+            // In double buffer mode a memory address update blocked by the dma controller sets TEIF,
+            // which is handled by the double buffer swap.
+            let dma_isr_teif = if dma_ccr.dbm().read_bit() {
+                None
+            } else {
+                Some(dma_isr_teif)
+            };
+            handle_dma_err::<DmaCh>(&val, dma_isr_dmeif, dma_isr_feif, dma_isr_teif);
+            fib::Yielded::<(), !>(())
+        });
+    }
+
+    /// Start a peripheral-to-memory transfer into `buf`.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must outlive the transfer.
+    pub unsafe fn start_rx(&self, buf: &mut [u8]) {
+        let addr = buf.as_mut_ptr() as usize;
+        self.start_at(addr as u32, self.minc, self.items(addr, buf.len()));
+    }
+
+    /// Start a memory-to-peripheral transfer from `buf`.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must outlive the transfer.
+    pub unsafe fn start_tx(&self, buf: &[u8]) {
+        let addr = buf.as_ptr() as usize;
+        self.start_at(addr as u32, self.minc, self.items(addr, buf.len()));
+    }
+
+    /// Start a transfer of `len` bytes to or from a fixed dummy word,
+    /// e.g. to clock out or discard data on a full-duplex peripheral.
+    ///
+    /// # Safety
+    ///
+    /// The stream must not transfer other data while the dummy transfer is in progress.
+    pub unsafe fn start_dummy(&self, len: usize) {
+        let addr = addr_of_mut!(DUMMY) as usize;
+        self.start_at(addr as u32, false, self.items(addr, len));
+    }
+
+    /// Get the peripheral data size, i.e. the size of the data items counted by NDTR.
+    pub fn data_size(&self) -> DmaDataSize {
+        self.psize
+    }
+
+    /// Get the number of peripheral data items in `len` bytes to be transferred to or from memory at `addr`.
+    /// The circular or double buffer mode must be configured before the items are counted.
+    pub(crate) fn items(&self, addr: usize, len: usize) -> usize {
+        let circ = self.dma_ccr.circ().read_bit() || self.dma_ccr.dbm().read_bit();
+        check_memory(addr, len, self.msize, self.fifo, self.mburst, circ);
+        ndt_items(len, self.psize)
+    }
+
+    unsafe fn start_at(&self, addr: u32, minc: bool, len: usize) {
+        self.dma_ccr.modify_reg(|r, v| {
+            if minc {
+                r.minc().set(v);
+            } else {
+                r.minc().clear(v);
+            }
+            r.dbm().clear(v); // single buffer mode
+        });
+
+        // Set buffer memory addres.
+        self.dma_cm0ar.store_reg(|r, v| {
+            r.m0a().write(v, addr);
+        });
+
+        // Set number of data items to transfer.
+        self.dma_cndtr.store_reg(|r, v| {
+            r.ndt().write(v, len as u32);
+        });
+
+        // Clear transfer completed and fifo error interrupt flags.
+        self.dma_ifcr_ctcif.set_bit();
+        self.dma_ifcr_cfeif.set_bit();
+
+        // Enable stream.
+        self.dma_ccr.modify_reg(|r, v| r.en().set(v));
+    }
+
+    /// Get a future that completes when the transfer has completed.
+    /// The future must be created before the transfer is started.
+    pub fn transfer_complete(&self) -> FiberFuture<()> {
+        let dma_isr_tcif = self.dma_isr_tcif;
+        let dma_ifcr_ctcif = self.dma_ifcr_ctcif;
+        self.dma_int.add_future(fib::new_fn(move || {
+            if dma_isr_tcif.read_bit() {
+                // Clear transfer completed interrupt flag.
+                dma_ifcr_ctcif.set_bit();
+                fib::Complete(())
+            } else {
+                fib::Yielded(())
+            }
+        }))
+    }
+
+    /// Disable the stream and wait for any ongoing data item transfer to complete.
+    pub fn stop(&self) {
+        self.dma_ccr.modify_reg(|r, v| r.en().clear(v));
+
+        // Wait until the EN bit in DMA_SxCR register is reset.
+        while self.dma_ccr.en().read_bit() {}
+    }
+
+    /// Get the number of data items remaining in the current transfer.
+    pub fn remaining(&self) -> usize {
+        self.dma_cndtr.ndt().read_bits() as usize
+    }
+
+    /// Get the number of data register token, e.g. for polling the transfer progress from a fiber.
+    pub fn ndtr(&self) -> DmaCh::CDmaCndtr {
+        self.dma_cndtr
+    }
+
+    /// Get whether the transfer complete interrupt flag is asserted.
+    pub fn is_complete(&self) -> bool {
+        self.dma_isr_tcif.read_bit()
+    }
+
+    /// Clear the transfer complete interrupt flag.
+    pub fn clear_complete(&self) {
+        self.dma_ifcr_ctcif.set_bit();
+    }
+}
+
+pub(crate) fn handle_dma_err<DmaCh: DmaChMap>(
+    val: &DmaCh::DmaIsrVal,
+    dma_isr_dmeif: DmaCh::CDmaIsrDmeif,
+    dma_isr_feif: DmaCh::CDmaIsrFeif,
+    dma_isr_teif: Option<DmaCh::CDmaIsrTeif>,
+) {
+    if let Some(dma_isr_teif) = dma_isr_teif {
+        if dma_isr_teif.read(&val) {
+            panic!("Transfer error");
+        }
+    }
+    if dma_isr_dmeif.read(&val) {
+        panic!("Direct mode error");
+    }
+    if dma_isr_feif.read(&val) {
+        panic!("FIFO error");
+    }
+}
+
+/// Check that `len` bytes of memory at `addr` can be transferred without leaving data in the fifo.
+fn check_memory(
+    addr: usize,
+    len: usize,
+    msize: DmaDataSize,
+    fifo: DmaFifo,
+    mburst: DmaBurst,
+    circ: bool,
+) {
+    // The memory address must be aligned to the memory data size.
+    assert_eq!(addr % msize.bytes(), 0);
+    if let DmaFifo::Fifo(_) = fifo {
+        // The fifo is only emptied in whole memory data items.
+        assert_eq!(len % msize.bytes(), 0);
+    }
+    if circ {
+        // The transfer is only reloaded after whole memory bursts.
+        assert_eq!(len % (mburst.beats() * msize.bytes()), 0);
+    }
+}
+
+/// Get the number of data items of `size` in `len` bytes, i.e. the NDTR value for a transfer of `len` bytes.
+fn ndt_items(len: usize, size: DmaDataSize) -> usize {
+    assert_eq!(len % size.bytes(), 0);
+    let items = len / size.bytes();
+    assert!(items <= 0xFFFF);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DmaFifoThreshold;
+
+    #[test]
+    fn data_size() {
+        assert_eq!(1, DmaDataSize::Byte.bytes());
+        assert_eq!(2, DmaDataSize::HalfWord.bytes());
+        assert_eq!(4, DmaDataSize::Word.bytes());
+        assert_eq!(0b00, DmaDataSize::Byte.bits());
+        assert_eq!(0b01, DmaDataSize::HalfWord.bits());
+        assert_eq!(0b10, DmaDataSize::Word.bits());
+    }
+
+    #[test]
+    fn stream_setup_defaults() {
+        let setup = DmaStreamSetup::new(DmaDir::PeriphToMem, 0x4000_4404);
+        assert!(setup.dir == DmaDir::PeriphToMem);
+        assert_eq!(0x4000_4404, setup.per_addr);
+        assert!(!setup.pinc);
+        assert!(setup.minc);
+        assert!(!setup.circ);
+        assert!(setup.tcie);
+    }
+
+    #[test]
+    fn ndt_items_are_peripheral_data_items() {
+        assert_eq!(6, ndt_items(6, DmaDataSize::Byte));
+        assert_eq!(3, ndt_items(6, DmaDataSize::HalfWord));
+        assert_eq!(2, ndt_items(8, DmaDataSize::Word));
+        assert_eq!(0xFFFF, ndt_items(0xFFFF, DmaDataSize::Byte));
+    }
+
+    #[test]
+    #[should_panic]
+    fn ndt_items_rejects_partial_items() {
+        ndt_items(5, DmaDataSize::HalfWord);
+    }
+
+    #[test]
+    #[should_panic]
+    fn ndt_items_rejects_too_many_items() {
+        ndt_items(0x1_0000, DmaDataSize::Byte);
+    }
+
+    #[test]
+    fn check_memory_accepts_whole_items_and_bursts() {
+        let fifo = DmaFifo::Fifo(DmaFifoThreshold::Full);
+        check_memory(
+            0x2000_0001,
+            3,
+            DmaDataSize::Byte,
+            DmaFifo::Direct,
+            DmaBurst::Single,
+            true,
+        );
+        check_memory(
+            0x2000_0004,
+            12,
+            DmaDataSize::Word,
+            fifo,
+            DmaBurst::Single,
+            false,
+        );
+        check_memory(
+            0x2000_0004,
+            32,
+            DmaDataSize::HalfWord,
+            fifo,
+            DmaBurst::Incr8,
+            true,
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn check_memory_rejects_unaligned_address() {
+        check_memory(
+            0x2000_0002,
+            8,
+            DmaDataSize::Word,
+            DmaFifo::Direct,
+            DmaBurst::Single,
+            false,
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn check_memory_rejects_partial_memory_items() {
+        let fifo = DmaFifo::Fifo(DmaFifoThreshold::Full);
+        check_memory(
+            0x2000_0000,
+            6,
+            DmaDataSize::Word,
+            fifo,
+            DmaBurst::Single,
+            false,
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn check_memory_rejects_partial_circular_bursts() {
+        let fifo = DmaFifo::Fifo(DmaFifoThreshold::Full);
+        check_memory(
+            0x2000_0000,
+            24,
+            DmaDataSize::HalfWord,
+            fifo,
+            DmaBurst::Incr8,
+            true,
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{periph::BackupPeriph, pwr::BackupDomainAccess, Pwr, Rcc};
+#[cfg(not(any(
+    stm32_mcu = "stm32f401",
+    stm32_mcu = "stm32f410",
+    stm32_mcu = "stm32f411",
+    stm32_mcu = "stm32f412",
+    stm32_mcu = "stm32f413",
+    stm32_mcu = "stm32f423",
+)))]
+use core::slice;
+use drone_cortexm::{reg::prelude::*, thr::prelude::*};
+
+/// Base address of the backup sram.
+#[cfg(not(any(
+    stm32_mcu = "stm32f401",
+    stm32_mcu = "stm32f410",
+    stm32_mcu = "stm32f411",
+    stm32_mcu = "stm32f412",
+    stm32_mcu = "stm32f413",
+    stm32_mcu = "stm32f423",
+)))]
+const BKPSRAM_BASE: usize = 0x4002_4000;
+/// Size of the backup sram in bytes.
+#[cfg(not(any(
+    stm32_mcu = "stm32f401",
+    stm32_mcu = "stm32f410",
+    stm32_mcu = "stm32f411",
+    stm32_mcu = "stm32f412",
+    stm32_mcu = "stm32f413",
+    stm32_mcu = "stm32f423",
+)))]
+pub const BKPSRAM_SIZE: usize = 4 * 1024;
+/// Number of RTC backup registers.
+pub const BACKUP_REG_COUNT: usize = 20;
+
+/// An RTC backup register.
+#[derive(Copy, Clone)]
+pub struct BackupReg(usize);
+
+impl BackupReg {
+    pub const fn new(index: usize) -> BackupReg {
+        assert!(index < BACKUP_REG_COUNT);
+        BackupReg(index)
+    }
+}
+
+/// Evaluate `$body` with `$reg` bound to the RTC_BKPxR register token with the given index.
+macro_rules! with_backup_reg {
+    ($backup:expr, $index:expr, |$reg:ident| $body:expr) => {
+        match $index {
+            0 => { let $reg = &$backup.rtc_bkp0r; $body }
+            1 => { let $reg = &$backup.rtc_bkp1r; $body }
+            2 => { let $reg = &$backup.rtc_bkp2r; $body }
+            3 => { let $reg = &$backup.rtc_bkp3r; $body }
+            4 => { let $reg = &$backup.rtc_bkp4r; $body }
+            5 => { let $reg = &$backup.rtc_bkp5r; $body }
+            6 => { let $reg = &$backup.rtc_bkp6r; $body }
+            7 => { let $reg = &$backup.rtc_bkp7r; $body }
+            8 => { let $reg = &$backup.rtc_bkp8r; $body }
+            9 => { let $reg = &$backup.rtc_bkp9r; $body }
+            10 => { let $reg = &$backup.rtc_bkp10r; $body }
+            11 => { let $reg = &$backup.rtc_bkp11r; $body }
+            12 => { let $reg = &$backup.rtc_bkp12r; $body }
+            13 => { let $reg = &$backup.rtc_bkp13r; $body }
+            14 => { let $reg = &$backup.rtc_bkp14r; $body }
+            15 => { let $reg = &$backup.rtc_bkp15r; $body }
+            16 => { let $reg = &$backup.rtc_bkp16r; $body }
+            17 => { let $reg = &$backup.rtc_bkp17r; $body }
+            18 => { let $reg = &$backup.rtc_bkp18r; $body }
+            19 => { let $reg = &$backup.rtc_bkp19r; $body }
+            _ => unreachable!(),
+        }
+    };
+}
+
+/// Backup domain driver for the RTC backup registers and, on parts that have it, the backup sram.
+pub struct BackupDomain {
+    backup: BackupPeriph,
+    _access: BackupDomainAccess,
+}
+
+impl BackupDomain {
+    /// Enable write access to the backup domain and, on parts that have it, the backup sram clock.
+    #[must_use]
+    pub fn with_enabled_clock(backup: BackupPeriph, pwr: &Pwr) -> BackupDomain {
+        let access = pwr.enable_backup_domain_access();
+        #[cfg(not(any(
+            stm32_mcu = "stm32f401",
+            stm32_mcu = "stm32f410",
+            stm32_mcu = "stm32f411",
+            stm32_mcu = "stm32f412",
+            stm32_mcu = "stm32f413",
+            stm32_mcu = "stm32f423",
+        )))]
+        backup.rcc_ahb1enr_bkpsramen.set_bit();
+        BackupDomain {
+            backup,
+            _access: access,
+        }
+    }
+
+    /// Read an RTC backup register.
+    pub fn read(&self, reg: BackupReg) -> u32 {
+        with_backup_reg!(self.backup, reg.0, |bkp| bkp.load().bkp())
+    }
+
+    /// Write an RTC backup register.
+    pub fn write(&mut self, reg: BackupReg, value: u32) {
+        with_backup_reg!(self.backup, reg.0, |bkp| bkp.store(|r| r.write_bkp(value)))
+    }
+
+    /// Reset the backup domain, i.e. the RTC, the LSE, the RTC clock source selection, and the RTC backup registers.
+    /// Note that the backup sram is not affected.
+    pub fn reset<RccInt: IntToken>(&mut self, rcc: &Rcc<RccInt>) {
+        rcc.rcc.rcc_bdcr.modify(|r| r.set_bdrst());
+        rcc.rcc.rcc_bdcr.modify(|r| r.clear_bdrst());
+    }
+}
+
+#[cfg(not(any(
+    stm32_mcu = "stm32f401",
+    stm32_mcu = "stm32f410",
+    stm32_mcu = "stm32f411",
+    stm32_mcu = "stm32f412",
+    stm32_mcu = "stm32f413",
+    stm32_mcu = "stm32f423",
+)))]
+impl BackupDomain {
+    /// Enable the backup regulator such that the backup sram content is retained in Standby and VBAT mode.
+    pub fn enable_backup_regulator(&self, pwr: &Pwr) {
+        pwr.pwr.pwr_csr.modify(|r| r.set_bre());
+        while !pwr.pwr.pwr_csr.brr.read_bit() {}
+    }
+
+    /// Disable the backup regulator.
+    /// The backup sram content is lost in Standby and VBAT mode.
+    pub fn disable_backup_regulator(&self, pwr: &Pwr) {
+        pwr.pwr.pwr_csr.modify(|r| r.clear_bre());
+    }
+
+    /// Get the backup sram.
+    pub fn sram(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(BKPSRAM_BASE as *mut u8, BKPSRAM_SIZE) }
+    }
+}
+
+#[cfg(not(any(
+    stm32_mcu = "stm32f401",
+    stm32_mcu = "stm32f410",
+    stm32_mcu = "stm32f411",
+    stm32_mcu = "stm32f412",
+    stm32_mcu = "stm32f413",
+    stm32_mcu = "stm32f423",
+)))]
+impl Drop for BackupDomain {
+    fn drop(&mut self) {
+        self.backup.rcc_ahb1enr_bkpsramen.clear_bit();
+    }
+}
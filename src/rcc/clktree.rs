@@ -1,4 +1,5 @@
-use core::marker::PhantomData;
+use crate::traits::{HClkExt, VoltageRange};
+use core::{fmt, marker::PhantomData};
 
 #[cfg(any(
     stm32_mcu = "stm32f401",
@@ -18,11 +19,11 @@ use core::marker::PhantomData;
     stm32_mcu = "stm32f469",
     stm32_mcu = "stm32f479",
 ))]
-/// Minimum CPU clock frequency.
+/// Minimum CPU clock frequency when sourced by the pll, i.e. the minimum pll P output frequency.
 pub const SYSCLK_MIN: u32 = 24_000_000;
 
 #[cfg(any(stm32_mcu = "stm32f446",))]
-/// Minimum CPU clock frequency.
+/// Minimum CPU clock frequency when sourced by the pll, i.e. the minimum pll P output frequency.
 pub const SYSCLK_MIN: u32 = 12_500_000;
 
 #[cfg(any(stm32_mcu = "stm32f401",))]
@@ -90,6 +91,80 @@ pub const PCLK2_MAX: u32 = SYSCLK_MAX / 2;
 /// Maximum APB1, low speed peripheral clock frequency.
 pub const PCLK1_MAX: u32 = PCLK2_MAX / 2;
 
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+/// Maximum CPU clock frequency when over-drive is disabled.
+pub const SYSCLK_MAX_NO_OVERDRIVE: u32 = 168_000_000;
+
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+/// Maximum APB2, high speed peripheral clock frequency when over-drive is disabled.
+pub const PCLK2_MAX_NO_OVERDRIVE: u32 = SYSCLK_MAX_NO_OVERDRIVE / 2;
+
+#[cfg(not(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+)))]
+/// Maximum CPU clock frequency when over-drive is disabled.
+pub const SYSCLK_MAX_NO_OVERDRIVE: u32 = SYSCLK_MAX;
+
+#[cfg(not(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+)))]
+/// Maximum APB2, high speed peripheral clock frequency when over-drive is disabled.
+pub const PCLK2_MAX_NO_OVERDRIVE: u32 = PCLK2_MAX;
+
+/// Maximum APB1, low speed peripheral clock frequency when over-drive is disabled.
+pub const PCLK1_MAX_NO_OVERDRIVE: u32 = PCLK2_MAX_NO_OVERDRIVE / 2;
+
+#[cfg(any(
+    stm32_mcu = "stm32f401",
+    stm32_mcu = "stm32f405",
+    stm32_mcu = "stm32f407",
+    stm32_mcu = "stm32f415",
+    stm32_mcu = "stm32f417",
+))]
+/// Minimum vco output frequency.
+pub const PLL_VCO_MIN: u32 = 192_000_000;
+
+#[cfg(not(any(
+    stm32_mcu = "stm32f401",
+    stm32_mcu = "stm32f405",
+    stm32_mcu = "stm32f407",
+    stm32_mcu = "stm32f415",
+    stm32_mcu = "stm32f417",
+)))]
+/// Minimum vco output frequency.
+pub const PLL_VCO_MIN: u32 = 100_000_000;
+
+/// Maximum vco output frequency.
+pub const PLL_VCO_MAX: u32 = 432_000_000;
+
 pub trait Freq {
     /// Get the clock frequency.
     fn freq(&self) -> u32;
@@ -98,13 +173,16 @@ pub trait Freq {
 /// A peripheral clock token.
 pub trait PClkToken: Freq {}
 
+/// A timer kernel clock token.
+pub trait TimClkToken: Freq {}
+
 // Clock source selector
 pub struct Mux<Signal> {
     _signal: PhantomData<Signal>,
 }
 
 /// The High-Speed External (HSE) clock.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct HseClk(u32);
 
 impl HseClk {
@@ -124,8 +202,11 @@ impl Freq for HseClk {
     }
 }
 
+/// Maximum HSI trimming value (HSITRIM).
+pub const HSI_TRIM_MAX: u32 = 0b11111;
+
 /// The High-Speed Internal (HSI) 16MHz clock.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct HsiClk;
 
 impl HsiClk {
@@ -140,8 +221,192 @@ impl Freq for HsiClk {
     }
 }
 
-/// The pll clock source signal.
+/// The Low-Speed External (LSE) 32.768kHz clock.
+#[derive(Copy, Clone)]
+pub struct LseClk {
+    freq: u32,
+    /// Whether the oscillator is bypassed by an external clock.
+    pub bypass: bool,
+    /// The oscillator drive mode.
+    #[cfg(any(
+        stm32_mcu = "stm32f410",
+        stm32_mcu = "stm32f411",
+        stm32_mcu = "stm32f412",
+        stm32_mcu = "stm32f413",
+        stm32_mcu = "stm32f423",
+        stm32_mcu = "stm32f446",
+    ))]
+    pub drive: LseDrive,
+}
+
+/// The LSE oscillator drive mode.
+#[cfg(any(
+    stm32_mcu = "stm32f410",
+    stm32_mcu = "stm32f411",
+    stm32_mcu = "stm32f412",
+    stm32_mcu = "stm32f413",
+    stm32_mcu = "stm32f423",
+    stm32_mcu = "stm32f446",
+))]
+#[derive(Copy, Clone)]
+pub enum LseDrive {
+    LowPower,
+    HighDrive,
+}
+
+impl LseClk {
+    /// A 32.768kHz crystal.
+    pub const fn new() -> LseClk {
+        LseClk {
+            freq: 32_768,
+            bypass: false,
+            #[cfg(any(
+                stm32_mcu = "stm32f410",
+                stm32_mcu = "stm32f411",
+                stm32_mcu = "stm32f412",
+                stm32_mcu = "stm32f413",
+                stm32_mcu = "stm32f423",
+                stm32_mcu = "stm32f446",
+            ))]
+            drive: LseDrive::LowPower,
+        }
+    }
+
+    /// An external clock that bypasses the oscillator.
+    pub const fn new_bypass(freq: u32) -> LseClk {
+        assert!(freq <= 1_000_000);
+        LseClk {
+            freq,
+            bypass: true,
+            ..LseClk::new()
+        }
+    }
+
+    /// Set the oscillator drive mode.
+    #[cfg(any(
+        stm32_mcu = "stm32f410",
+        stm32_mcu = "stm32f411",
+        stm32_mcu = "stm32f412",
+        stm32_mcu = "stm32f413",
+        stm32_mcu = "stm32f423",
+        stm32_mcu = "stm32f446",
+    ))]
+    pub const fn with_drive(self, drive: LseDrive) -> LseClk {
+        assert!(!self.bypass);
+        LseClk { drive, ..self }
+    }
+
+    pub const fn f(&self) -> u32 {
+        self.freq
+    }
+}
+
+impl Freq for LseClk {
+    fn freq(&self) -> u32 {
+        self.f()
+    }
+}
+
+/// The Low-Speed Internal (LSI) ~32kHz clock.
+#[derive(Copy, Clone)]
+pub struct LsiClk;
+
+impl LsiClk {
+    pub const fn f(&self) -> u32 {
+        32_000
+    }
+}
+
+impl Freq for LsiClk {
+    fn freq(&self) -> u32 {
+        self.f()
+    }
+}
+
+/// The HSE clock divided for use by the RTC.
+#[derive(Copy, Clone)]
+pub struct HseRtcClk {
+    src: HseClk,
+    /// The HSE division factor for the RTC clock.
+    /// hse_rtc = hse / rtcpre
+    pub rtcpre: u32,
+}
+
+impl HseClk {
+    pub const fn to_hsertc(self, rtcpre: u32) -> HseRtcClk {
+        assert!(rtcpre >= 2 && rtcpre <= 31);
+        let clk = HseRtcClk { src: self, rtcpre };
+        assert!(clk.f() <= 1_000_000);
+        clk
+    }
+}
+
+impl HseRtcClk {
+    pub const fn f(&self) -> u32 {
+        self.src.f() / self.rtcpre
+    }
+}
+
+impl Freq for HseRtcClk {
+    fn freq(&self) -> u32 {
+        self.f()
+    }
+}
+
+/// The rtc clock source signal.
+#[derive(Copy, Clone)]
+pub enum RtcClkMuxSignal {
+    Lse(LseClk),
+    Lsi(LsiClk),
+    Hse(HseRtcClk),
+}
+
+/// The rtc clock source mux.
+pub const RTCCLK_MUX: Mux<RtcClkMuxSignal> = Mux {
+    _signal: PhantomData,
+};
+
+impl RtcClkMuxSignal {
+    pub const fn f(&self) -> u32 {
+        match self {
+            RtcClkMuxSignal::Lse(clk) => clk.f(),
+            RtcClkMuxSignal::Lsi(clk) => clk.f(),
+            RtcClkMuxSignal::Hse(clk) => clk.f(),
+        }
+    }
+
+    pub const fn to_rtcclk(self) -> RtcClk {
+        RtcClk { mux: self }
+    }
+}
+
+impl Freq for RtcClkMuxSignal {
+    fn freq(&self) -> u32 {
+        self.f()
+    }
+}
+
+/// The RTC clock.
 #[derive(Copy, Clone)]
+pub struct RtcClk {
+    /// The clock source.
+    pub mux: RtcClkMuxSignal,
+}
+
+impl RtcClk {
+    pub const fn f(&self) -> u32 {
+        self.mux.f()
+    }
+}
+
+impl Freq for RtcClk {
+    fn freq(&self) -> u32 {
+        self.f()
+    }
+}
+
+/// The pll clock source signal.
+#[derive(Copy, Clone, PartialEq)]
 pub enum PllSrcMuxSignal {
     Hsi(HsiClk),
     Hse(HseClk),
@@ -163,6 +428,61 @@ impl PllSrcMuxSignal {
     pub const fn to_pllsrc(self, pll_m: u32) -> PllSrc {
         PllSrc::new(self, pll_m)
     }
+
+    /// Search for the pll dividers m, n, p, and q that generate `sysclk` exactly on the pll P output.
+    /// Compilation fails with the closest achievable frequencies if no exact configuration exists.
+    pub const fn solve_pll(self, sysclk: u32, q_req: PllQReq) -> Pll {
+        let src = self.f();
+        let mut best_sysclk = 0;
+        let mut best_qclk = 0;
+        let mut best_err = u32::MAX;
+
+        // Prefer the highest vco input frequency (lowest m) as it gives the lowest pll jitter.
+        let mut m = PLL_M_MIN;
+        while m <= PLL_M_MAX {
+            let vcoin = src / m;
+            if src % m != 0 || vcoin < PLL_VCOIN_MIN || vcoin > PLL_VCOIN_MAX {
+                m += 1;
+                continue;
+            }
+
+            let mut i = 0;
+            while i < PLL_P_DIVS.len() {
+                let p = PLL_P_DIVS[i];
+                // Try the two n values that give a vco output on each side of the target.
+                let n_floor = (sysclk as u64 * p as u64 / vcoin as u64) as u32;
+                let mut n = n_floor;
+                while n <= n_floor + 1 {
+                    if n < PLL_N_MIN || n > PLL_N_MAX {
+                        n += 1;
+                        continue;
+                    }
+                    let vco = vcoin * n;
+                    if vco < PLL_VCO_MIN || vco > PLL_VCO_MAX {
+                        n += 1;
+                        continue;
+                    }
+
+                    let f = vco / p;
+                    let q = q_req.div(vco);
+                    let err = if f > sysclk { f - sysclk } else { sysclk - f };
+                    if err == 0 && vco % p == 0 && q_req.is_met(vco, q) {
+                        return self.to_pllsrc(m).to_pll(n, p, q);
+                    }
+                    if err < best_err {
+                        best_err = err;
+                        best_sysclk = f;
+                        best_qclk = vco / q;
+                    }
+                    n += 1;
+                }
+                i += 1;
+            }
+            m += 1;
+        }
+
+        pll_solve_failed(best_sysclk, best_qclk)
+    }
 }
 
 impl Freq for PllSrcMuxSignal {
@@ -171,8 +491,88 @@ impl Freq for PllSrcMuxSignal {
     }
 }
 
-/// The pll input clock (vcoin).
+/// Minimum pll input clock division factor.
+pub const PLL_M_MIN: u32 = 2;
+/// Maximum pll input clock division factor.
+pub const PLL_M_MAX: u32 = 63;
+/// Minimum pll multiplication factor for vco.
+pub const PLL_N_MIN: u32 = 50;
+/// Maximum pll multiplication factor for vco.
+pub const PLL_N_MAX: u32 = 432;
+/// Valid pll division factors for the system clock.
+pub const PLL_P_DIVS: [u32; 4] = [2, 4, 6, 8];
+/// Minimum pll division factor for usb, sdio, and rng.
+pub const PLL_Q_MIN: u32 = 2;
+/// Maximum pll division factor for usb, sdio, and rng.
+pub const PLL_Q_MAX: u32 = 15;
+/// Minimum vco input frequency.
+pub const PLL_VCOIN_MIN: u32 = 1_000_000;
+/// Maximum vco input frequency.
+pub const PLL_VCOIN_MAX: u32 = 2_000_000;
+/// The usb, sdio, and rng clock frequency.
+pub const PLL48CLK: u32 = 48_000_000;
+
+/// The requirement to the pll Q output used when solving for the pll dividers.
 #[derive(Copy, Clone)]
+pub enum PllQReq {
+    /// The Q output must be exactly 48MHz, e.g. when usb is used.
+    Exact48MHz,
+    /// The Q output may be anything up to 48MHz.
+    Max48MHz,
+}
+
+impl PllQReq {
+    /// Get the q divider to use for a given vco output frequency.
+    const fn div(&self, vco: u32) -> u32 {
+        let q = match self {
+            PllQReq::Exact48MHz => (vco + PLL48CLK / 2) / PLL48CLK,
+            PllQReq::Max48MHz => (vco + PLL48CLK - 1) / PLL48CLK,
+        };
+        if q < PLL_Q_MIN {
+            PLL_Q_MIN
+        } else if q > PLL_Q_MAX {
+            PLL_Q_MAX
+        } else {
+            q
+        }
+    }
+
+    const fn is_met(&self, vco: u32, q: u32) -> bool {
+        match self {
+            PllQReq::Exact48MHz => vco % q == 0 && vco / q == PLL48CLK,
+            PllQReq::Max48MHz => vco / q <= PLL48CLK,
+        }
+    }
+}
+
+/// Fail const evaluation with a message containing the closest achievable frequencies.
+const fn pll_solve_failed(sysclk: u32, qclk: u32) -> ! {
+    // The frequencies are right aligned in nine blank slots following their prefixes.
+    const SYSCLK_END: usize = "No exact pll configuration exists, closest is sysclk=".len() + 9;
+    const QCLK_END: usize = SYSCLK_END + "Hz, q=".len() + 9;
+    let mut msg = *b"No exact pll configuration exists, closest is sysclk=         Hz, q=         Hz";
+    msg = fmt_u32(msg, SYSCLK_END, sysclk);
+    msg = fmt_u32(msg, QCLK_END, qclk);
+    // The message only contains ascii characters.
+    panic!("{}", unsafe { core::str::from_utf8_unchecked(&msg) });
+}
+
+/// Write `value` right aligned into `buf` so that the last digit is placed at `end - 1`.
+const fn fmt_u32<const N: usize>(mut buf: [u8; N], end: usize, mut value: u32) -> [u8; N] {
+    let mut i = end;
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    buf
+}
+
+/// The pll input clock (vcoin).
+#[derive(Copy, Clone, PartialEq)]
 pub struct PllSrc {
     /// The pll input clock source.
     pub mux: PllSrcMuxSignal,
@@ -184,8 +584,11 @@ pub struct PllSrc {
 impl PllSrc {
     #[must_use]
     const fn new(mux: PllSrcMuxSignal, m: u32) -> PllSrc {
-        assert!(m >= 2 && m <= 63);
-        PllSrc { mux, m }
+        assert!(m >= PLL_M_MIN && m <= PLL_M_MAX);
+        let src = PllSrc { mux, m };
+        let f = src.f();
+        assert!(f >= PLL_VCOIN_MIN && f <= PLL_VCOIN_MAX);
+        src
     }
 
     pub const fn f(&self) -> u32 {
@@ -196,6 +599,49 @@ impl PllSrc {
         let vco = PllVco::new(self, pll_n);
         Pll::new(vco, pll_p, pll_q)
     }
+
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f446",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    pub const fn to_plli2s(self, plli2s_n: u32, plli2s_q: u32, plli2s_r: u32) -> PllI2s {
+        let vco = PllVco::new(self, plli2s_n);
+        PllI2s::new(vco, plli2s_q, plli2s_r)
+    }
+
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+    ))]
+    pub const fn to_pllsai(self, pllsai_n: u32, pllsai_q: u32, pllsai_r: u32) -> PllSai {
+        let vco = PllVco::new(self, pllsai_n);
+        PllSai::new(vco, pllsai_q, pllsai_r)
+    }
+
+    #[cfg(any(stm32_mcu = "stm32f469", stm32_mcu = "stm32f479"))]
+    pub const fn to_pllsai(
+        self,
+        pllsai_n: u32,
+        pllsai_p: u32,
+        pllsai_q: u32,
+        pllsai_r: u32,
+    ) -> PllSai {
+        let vco = PllVco::new(self, pllsai_n);
+        PllSai::new(vco, pllsai_p, pllsai_q, pllsai_r)
+    }
+
+    #[cfg(stm32_mcu = "stm32f446")]
+    pub const fn to_pllsai(self, pllsai_n: u32, pllsai_p: u32, pllsai_q: u32) -> PllSai {
+        let vco = PllVco::new(self, pllsai_n);
+        PllSai::new(vco, pllsai_p, pllsai_q)
+    }
 }
 
 impl Freq for PllSrc {
@@ -207,7 +653,7 @@ impl Freq for PllSrc {
 /// The clocks generated by the pll.
 /// pllp: pllclk = vcoin * n / p
 /// pllq: vcoin * n / q
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct PllVco {
     /// The pll input clock signal (vcoin).
     pub src: PllSrc,
@@ -219,8 +665,11 @@ pub struct PllVco {
 impl PllVco {
     #[must_use]
     const fn new(src: PllSrc, n: u32) -> PllVco {
-        assert!(n >= 50 && n <= 432);
-        PllVco { src, n }
+        assert!(n >= PLL_N_MIN && n <= PLL_N_MAX);
+        let vco = PllVco { src, n };
+        let f = vco.f();
+        assert!(f >= PLL_VCO_MIN && f <= PLL_VCO_MAX);
+        vco
     }
 
     pub const fn f(&self) -> u32 {
@@ -234,13 +683,13 @@ impl Freq for PllVco {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct PllP;
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct PllQ;
 
 /// A pll generated clock, e.g. PllClk<PllP> = vcoin * n / p
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct PllClk<Out> {
     _out: PhantomData<Out>,
     src: PllVco,
@@ -259,7 +708,7 @@ impl<Out> Freq for PllClk<Out> {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct Pll {
     pub vco: PllVco,
     /// Pll division factor for system clock.
@@ -272,7 +721,7 @@ impl Pll {
     #[must_use]
     const fn new(vco: PllVco, p: u32, q: u32) -> Pll {
         assert!(p == 2 || p == 4 || p == 6 || p == 8);
-        assert!(q >= 2 && q <= 15);
+        assert!(q >= PLL_Q_MIN && q <= PLL_Q_MAX);
         let pll = Pll {
             vco,
             p: PllClk {
@@ -286,11 +735,271 @@ impl Pll {
                 div: q,
             },
         };
-        assert!(pll.q.f() <= 48_000_000);
+        let p = pll.p.f();
+        assert!(p >= SYSCLK_MIN && p <= SYSCLK_MAX);
+        assert!(pll.q.f() <= PLL48CLK);
         pll
     }
 }
 
+#[derive(Copy, Clone)]
+pub struct PllI2sQ;
+#[derive(Copy, Clone)]
+pub struct PllI2sR;
+
+/// The I2S pll (PLLI2S).
+/// The pll shares its input clock (vcoin) with the main pll,
+/// except on the stm32f446 where it has its own m divider.
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+#[derive(Copy, Clone)]
+pub struct PllI2s {
+    pub vco: PllVco,
+    /// Pll division factor for the sai clock.
+    pub q: PllClk<PllI2sQ>,
+    /// Pll division factor for the i2s clock.
+    pub r: PllClk<PllI2sR>,
+}
+
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl PllI2s {
+    #[must_use]
+    const fn new(vco: PllVco, q: u32, r: u32) -> PllI2s {
+        assert!(q >= 2 && q <= 15);
+        assert!(r >= 2 && r <= 7);
+        let plli2s = PllI2s {
+            vco,
+            q: PllClk {
+                _out: PhantomData,
+                src: vco,
+                div: q,
+            },
+            r: PllClk {
+                _out: PhantomData,
+                src: vco,
+                div: r,
+            },
+        };
+        assert!(plli2s.r.f() <= 192_000_000);
+        plli2s
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct PllSaiP;
+#[derive(Copy, Clone)]
+pub struct PllSaiQ;
+#[derive(Copy, Clone)]
+pub struct PllSaiR;
+
+/// The SAI pll (PLLSAI).
+/// The pll shares its input clock (vcoin) with the main pll,
+/// except on the stm32f446 where it has its own m divider.
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+#[derive(Copy, Clone)]
+pub struct PllSai {
+    pub vco: PllVco,
+    /// Pll division factor for the usb, sdio, and rng clock.
+    #[cfg(any(
+        stm32_mcu = "stm32f446",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    pub p: PllClk<PllSaiP>,
+    /// Pll division factor for the sai clock.
+    pub q: PllClk<PllSaiQ>,
+    /// Pll division factor for the lcd clock.
+    #[cfg(not(stm32_mcu = "stm32f446"))]
+    pub r: PllClk<PllSaiR>,
+    /// The lcd-tft clock divided from the r output.
+    #[cfg(not(stm32_mcu = "stm32f446"))]
+    pub ltdc: LtdcClk,
+}
+
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+))]
+impl PllSai {
+    #[must_use]
+    const fn new(vco: PllVco, q: u32, r: u32) -> PllSai {
+        assert!(q >= 2 && q <= 15);
+        assert!(r >= 2 && r <= 7);
+        let r = PllClk {
+            _out: PhantomData,
+            src: vco,
+            div: r,
+        };
+        let pllsai = PllSai {
+            vco,
+            q: PllClk {
+                _out: PhantomData,
+                src: vco,
+                div: q,
+            },
+            r,
+            ltdc: LtdcClk::new(r, 2),
+        };
+        assert!(pllsai.q.f() <= 216_000_000);
+        assert!(pllsai.r.f() <= 216_000_000);
+        pllsai
+    }
+}
+
+#[cfg(any(stm32_mcu = "stm32f469", stm32_mcu = "stm32f479"))]
+impl PllSai {
+    #[must_use]
+    const fn new(vco: PllVco, p: u32, q: u32, r: u32) -> PllSai {
+        assert!(p == 2 || p == 4 || p == 6 || p == 8);
+        assert!(q >= 2 && q <= 15);
+        assert!(r >= 2 && r <= 7);
+        let r = PllClk {
+            _out: PhantomData,
+            src: vco,
+            div: r,
+        };
+        let pllsai = PllSai {
+            vco,
+            p: PllClk {
+                _out: PhantomData,
+                src: vco,
+                div: p,
+            },
+            q: PllClk {
+                _out: PhantomData,
+                src: vco,
+                div: q,
+            },
+            r,
+            ltdc: LtdcClk::new(r, 2),
+        };
+        assert!(pllsai.p.f() <= PLL48CLK);
+        assert!(pllsai.q.f() <= 216_000_000);
+        assert!(pllsai.r.f() <= 216_000_000);
+        pllsai
+    }
+}
+
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl PllSai {
+    /// Use a different lcd-tft clock division factor, where the default is 2.
+    pub const fn with_ltdc_div(self, divr: u32) -> PllSai {
+        PllSai {
+            ltdc: LtdcClk::new(self.r, divr),
+            ..self
+        }
+    }
+}
+
+/// The lcd-tft clock (LCD_CLK).
+/// ltdcclk = pllsair / divr
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+#[derive(Copy, Clone)]
+pub struct LtdcClk {
+    /// The clock source.
+    src: PllClk<PllSaiR>,
+    /// The clock division factor, i.e. PLLSAIDIVR.
+    pub divr: u32,
+}
+
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl LtdcClk {
+    #[must_use]
+    const fn new(src: PllClk<PllSaiR>, divr: u32) -> LtdcClk {
+        assert!(divr == 2 || divr == 4 || divr == 8 || divr == 16);
+        LtdcClk { src, divr }
+    }
+
+    pub const fn f(&self) -> u32 {
+        self.src.f() / self.divr
+    }
+}
+
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl Freq for LtdcClk {
+    fn freq(&self) -> u32 {
+        self.f()
+    }
+}
+
+#[cfg(stm32_mcu = "stm32f446")]
+impl PllSai {
+    #[must_use]
+    const fn new(vco: PllVco, p: u32, q: u32) -> PllSai {
+        assert!(p == 2 || p == 4 || p == 6 || p == 8);
+        assert!(q >= 2 && q <= 15);
+        let pllsai = PllSai {
+            vco,
+            p: PllClk {
+                _out: PhantomData,
+                src: vco,
+                div: p,
+            },
+            q: PllClk {
+                _out: PhantomData,
+                src: vco,
+                div: q,
+            },
+        };
+        assert!(pllsai.p.f() <= PLL48CLK);
+        assert!(pllsai.q.f() <= 216_000_000);
+        pllsai
+    }
+}
+
 /// The system clock source mux.
 
 /// The system clock source signal.
@@ -314,8 +1023,25 @@ impl SysClkMuxSignal {
         }
     }
 
+    /// Use the signal as sysclk with the regulator over-drive mode disabled.
     pub const fn to_sysclk(self) -> SysClk {
-        SysClk::new(self)
+        SysClk::new(self, false)
+    }
+
+    /// Use the signal as sysclk with the regulator over-drive mode enabled.
+    ///
+    /// The over-drive mode must be enabled before the sysclk is switched to the pll.
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f446",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    pub const fn to_sysclk_overdrive(self) -> SysClk {
+        SysClk::new(self, true)
     }
 }
 
@@ -329,15 +1055,21 @@ impl Freq for SysClkMuxSignal {
 #[derive(Copy, Clone)]
 pub struct SysClk {
     /// The clock source.
-    mux: SysClkMuxSignal,
+    pub(crate) mux: SysClkMuxSignal,
+    /// Whether the frequencies require the regulator over-drive mode.
+    pub overdrive: bool,
 }
 
 impl SysClk {
     #[must_use]
-    const fn new(mux: SysClkMuxSignal) -> SysClk {
-        let sysclk = SysClk { mux };
+    const fn new(mux: SysClkMuxSignal, overdrive: bool) -> SysClk {
+        let sysclk = SysClk { mux, overdrive };
         let f = sysclk.f();
-        assert!(f >= SYSCLK_MIN && f <= SYSCLK_MAX);
+        if overdrive {
+            assert!(f <= SYSCLK_MAX);
+        } else {
+            assert!(f <= SYSCLK_MAX_NO_OVERDRIVE);
+        }
         sysclk
     }
 
@@ -410,7 +1142,11 @@ impl PClk1 {
     const fn new(src: HClk, ppre1: u32) -> PClk1 {
         assert!(ppre1 == 1 || ppre1 == 2 || ppre1 == 4 || ppre1 == 8 || ppre1 == 16);
         let pclk1 = PClk1 { src, ppre1 };
-        assert!(pclk1.f() <= PCLK1_MAX);
+        if src.src.overdrive {
+            assert!(pclk1.f() <= PCLK1_MAX);
+        } else {
+            assert!(pclk1.f() <= PCLK1_MAX_NO_OVERDRIVE);
+        }
         pclk1
     }
 
@@ -443,7 +1179,11 @@ impl PClk2 {
     const fn new(src: HClk, ppre2: u32) -> PClk2 {
         assert!(ppre2 == 1 || ppre2 == 2 || ppre2 == 4 || ppre2 == 8 || ppre2 == 16);
         let pclk2 = PClk2 { src, ppre2 };
-        assert!(pclk2.f() <= PCLK2_MAX);
+        if src.src.overdrive {
+            assert!(pclk2.f() <= PCLK2_MAX);
+        } else {
+            assert!(pclk2.f() <= PCLK2_MAX_NO_OVERDRIVE);
+        }
         pclk2
     }
 
@@ -458,6 +1198,119 @@ impl Freq for PClk2 {
     }
 }
 
+/// The timers clock prescaler selection (TIMPRE).
+#[derive(Copy, Clone)]
+pub enum TimPre {
+    /// The timer clock is PCLK if the APB prescaler is 1, otherwise it is 2 x PCLK (TIMPRE=0).
+    Mul2,
+    /// The timer clock is HCLK if the APB prescaler is 1, 2, or 4, otherwise it is 4 x PCLK (TIMPRE=1).
+    #[cfg(not(any(
+        stm32_mcu = "stm32f405",
+        stm32_mcu = "stm32f407",
+        stm32_mcu = "stm32f415",
+        stm32_mcu = "stm32f417",
+    )))]
+    Mul4,
+}
+
+impl TimPre {
+    /// Get the timer kernel clock frequency for an APB bus clock.
+    const fn timclk_f(&self, hclk: u32, ppre: u32) -> u32 {
+        match self {
+            TimPre::Mul2 => {
+                if ppre == 1 {
+                    hclk
+                } else {
+                    2 * hclk / ppre
+                }
+            }
+            #[cfg(not(any(
+                stm32_mcu = "stm32f405",
+                stm32_mcu = "stm32f407",
+                stm32_mcu = "stm32f415",
+                stm32_mcu = "stm32f417",
+            )))]
+            TimPre::Mul4 => {
+                if ppre <= 4 {
+                    hclk
+                } else {
+                    4 * hclk / ppre
+                }
+            }
+        }
+    }
+}
+
+impl PClk1 {
+    /// Get the kernel clock for the timers on APB1 with TIMPRE=0.
+    pub const fn to_timclk(self) -> TimClk1 {
+        self.to_timclk_with(TimPre::Mul2)
+    }
+
+    /// Get the kernel clock for the timers on APB1.
+    /// Note that the TIMPRE setting is shared among the APB1 and APB2 timers.
+    pub const fn to_timclk_with(self, timpre: TimPre) -> TimClk1 {
+        TimClk1 { src: self, timpre }
+    }
+}
+
+impl PClk2 {
+    /// Get the kernel clock for the timers on APB2 with TIMPRE=0.
+    pub const fn to_timclk(self) -> TimClk2 {
+        self.to_timclk_with(TimPre::Mul2)
+    }
+
+    /// Get the kernel clock for the timers on APB2.
+    /// Note that the TIMPRE setting is shared among the APB1 and APB2 timers.
+    pub const fn to_timclk_with(self, timpre: TimPre) -> TimClk2 {
+        TimClk2 { src: self, timpre }
+    }
+}
+
+/// The kernel clock for the timers on APB1 (TIMCLK1).
+#[derive(Copy, Clone)]
+pub struct TimClk1 {
+    src: PClk1,
+    /// The timers clock prescaler selection.
+    pub timpre: TimPre,
+}
+
+impl TimClk1 {
+    pub const fn f(&self) -> u32 {
+        self.timpre.timclk_f(self.src.src.f(), self.src.ppre1)
+    }
+}
+
+impl TimClkToken for TimClk1 {}
+
+impl Freq for TimClk1 {
+    fn freq(&self) -> u32 {
+        self.f()
+    }
+}
+
+/// The kernel clock for the timers on APB2 (TIMCLK2).
+#[derive(Copy, Clone)]
+pub struct TimClk2 {
+    src: PClk2,
+    /// The timers clock prescaler selection.
+    pub timpre: TimPre,
+}
+
+impl TimClk2 {
+    pub const fn f(&self) -> u32 {
+        self.timpre.timclk_f(self.src.src.f(), self.src.ppre2)
+    }
+}
+
+impl TimClkToken for TimClk2 {}
+
+impl Freq for TimClk2 {
+    fn freq(&self) -> u32 {
+        self.f()
+    }
+}
+
 /// The Cortex System Timer Clock.
 #[derive(Copy, Clone)]
 pub struct SysTickClk {
@@ -475,3 +1328,333 @@ impl Freq for SysTickClk {
         self.f()
     }
 }
+
+/// The main regulator output voltage scale (VOS).
+/// A lower scale reduces the consumption but also the maximum sysclk frequency.
+#[derive(Copy, Clone, PartialEq)]
+pub enum VoltageScale {
+    #[cfg(not(stm32_mcu = "stm32f401"))]
+    Scale1,
+    Scale2,
+    #[cfg(not(any(
+        stm32_mcu = "stm32f405",
+        stm32_mcu = "stm32f407",
+        stm32_mcu = "stm32f415",
+        stm32_mcu = "stm32f417",
+    )))]
+    Scale3,
+}
+
+impl VoltageScale {
+    /// The voltage scale that supports the maximum sysclk frequency.
+    pub const fn highest() -> VoltageScale {
+        #[cfg(stm32_mcu = "stm32f401")]
+        let vos = VoltageScale::Scale2;
+        #[cfg(not(stm32_mcu = "stm32f401"))]
+        let vos = VoltageScale::Scale1;
+        vos
+    }
+
+    /// Get the maximum sysclk frequency for the voltage scale.
+    #[cfg(stm32_mcu = "stm32f401")]
+    pub const fn sysclk_max(&self, _overdrive: bool) -> u32 {
+        match self {
+            VoltageScale::Scale2 => 84_000_000,
+            VoltageScale::Scale3 => 60_000_000,
+        }
+    }
+
+    /// Get the maximum sysclk frequency for the voltage scale.
+    #[cfg(any(
+        stm32_mcu = "stm32f405",
+        stm32_mcu = "stm32f407",
+        stm32_mcu = "stm32f415",
+        stm32_mcu = "stm32f417",
+    ))]
+    pub const fn sysclk_max(&self, _overdrive: bool) -> u32 {
+        match self {
+            VoltageScale::Scale1 => 168_000_000,
+            VoltageScale::Scale2 => 144_000_000,
+        }
+    }
+
+    /// Get the maximum sysclk frequency for the voltage scale.
+    #[cfg(any(
+        stm32_mcu = "stm32f410",
+        stm32_mcu = "stm32f411",
+        stm32_mcu = "stm32f412",
+        stm32_mcu = "stm32f413",
+        stm32_mcu = "stm32f423",
+    ))]
+    pub const fn sysclk_max(&self, _overdrive: bool) -> u32 {
+        match self {
+            VoltageScale::Scale1 => 100_000_000,
+            VoltageScale::Scale2 => 84_000_000,
+            VoltageScale::Scale3 => 64_000_000,
+        }
+    }
+
+    /// Get the maximum sysclk frequency for the voltage scale.
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f446",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    pub const fn sysclk_max(&self, overdrive: bool) -> u32 {
+        match (self, overdrive) {
+            (VoltageScale::Scale1, true) => 180_000_000,
+            (VoltageScale::Scale1, false) => 168_000_000,
+            (VoltageScale::Scale2, true) => 168_000_000,
+            (VoltageScale::Scale2, false) => 144_000_000,
+            (VoltageScale::Scale3, _) => 120_000_000,
+        }
+    }
+}
+
+/// A complete description of the core clock tree that can be applied by `Rcc::apply()`.
+#[derive(Copy, Clone)]
+pub struct ClockTree {
+    /// The main pll, if it should be enabled.
+    pub pll: Option<Pll>,
+    /// The APB1 peripheral clock.
+    pub pclk1: PClk1,
+    /// The APB2 peripheral clock.
+    pub pclk2: PClk2,
+    /// The timers clock prescaler selection.
+    pub timpre: TimPre,
+    /// The supply voltage range used to determine the flash wait states.
+    pub voltage: VoltageRange,
+    /// The main regulator output voltage scale.
+    pub vos: VoltageScale,
+}
+
+impl ClockTree {
+    /// Describe a clock tree with the timers prescaler TIMPRE=0, a supply voltage in the 2.7V-3.6V range,
+    /// and the highest regulator voltage scale.
+    /// `pll` must be given if sysclk is sourced by the pll, and `pclk1` and `pclk2` must be derived from the same hclk.
+    pub const fn new(pll: Option<Pll>, pclk1: PClk1, pclk2: PClk2) -> ClockTree {
+        let hclk1 = pclk1.src;
+        let hclk2 = pclk2.src;
+        assert!(hclk1.hpre == hclk2.hpre && hclk1.f() == hclk2.f());
+        assert!(hclk1.src.overdrive == hclk2.src.overdrive);
+
+        match (hclk1.src.mux, hclk2.src.mux) {
+            (SysClkMuxSignal::Hsi(_), SysClkMuxSignal::Hsi(_)) => {}
+            (SysClkMuxSignal::Hse(_), SysClkMuxSignal::Hse(_)) => {}
+            (SysClkMuxSignal::Pll(_), SysClkMuxSignal::Pll(_)) => {}
+            _ => panic!("PCLK1 and PCLK2 must be derived from the same sysclk."),
+        }
+
+        match (hclk1.src.mux, pll) {
+            (SysClkMuxSignal::Pll(p), Some(pll)) => {
+                assert!(p.div == pll.p.div && p.src.n == pll.vco.n);
+                assert!(p.src.src.m == pll.vco.src.m && p.src.src.f() == pll.vco.src.f());
+            }
+            (SysClkMuxSignal::Pll(_), None) => panic!("The pll must be given when it sources sysclk."),
+            (SysClkMuxSignal::Hse(hse), Some(pll)) => {
+                if let PllSrcMuxSignal::Hse(pllsrc) = pll.vco.src.mux {
+                    assert!(hse.f() == pllsrc.f());
+                }
+            }
+            _ => {}
+        }
+
+        ClockTree {
+            pll,
+            pclk1,
+            pclk2,
+            timpre: TimPre::Mul2,
+            voltage: VoltageRange::HighVoltage,
+            vos: VoltageScale::highest(),
+        }
+    }
+
+    /// Use a different timers clock prescaler selection.
+    pub const fn with_timpre(self, timpre: TimPre) -> ClockTree {
+        ClockTree { timpre, ..self }
+    }
+
+    /// Use a different supply voltage range.
+    pub const fn with_voltage(self, voltage: VoltageRange) -> ClockTree {
+        ClockTree { voltage, ..self }
+    }
+
+    /// Use a different regulator voltage scale.
+    pub const fn with_voltage_scale(self, vos: VoltageScale) -> ClockTree {
+        let sysclk = self.sysclk();
+        assert!(sysclk.f() <= vos.sysclk_max(sysclk.overdrive));
+        ClockTree { vos, ..self }
+    }
+
+    /// Get the tree that is in effect after the clock security system has detected a HSE failure,
+    /// i.e. with sysclk sourced by the HSI using the same bus prescalers, and without a pll that runs from the HSE.
+    pub const fn to_hsi_fallback(self) -> ClockTree {
+        let pll = match self.pll {
+            Some(pll) if matches!(pll.vco.src.mux, PllSrcMuxSignal::Hsi(_)) => Some(pll),
+            _ => None,
+        };
+        let hclk = SysClkMuxSignal::Hsi(HsiClk).to_sysclk().to_hclk(self.hclk().hpre);
+        ClockTree {
+            pll,
+            pclk1: hclk.to_pclk1(self.pclk1.ppre1),
+            pclk2: hclk.to_pclk2(self.pclk2.ppre2),
+            ..self
+        }
+    }
+
+    /// Get the HSE if it is used by either the pll or sysclk.
+    pub const fn hse(&self) -> Option<HseClk> {
+        if let SysClkMuxSignal::Hse(hse) = self.sysclk().mux {
+            return Some(hse);
+        }
+        if let Some(pll) = self.pll {
+            if let PllSrcMuxSignal::Hse(hse) = pll.vco.src.mux {
+                return Some(hse);
+            }
+        }
+        None
+    }
+
+    pub const fn sysclk(&self) -> SysClk {
+        self.pclk1.src.src
+    }
+
+    pub const fn hclk(&self) -> HClk {
+        self.pclk1.src
+    }
+
+    pub const fn timclk1(&self) -> TimClk1 {
+        self.pclk1.to_timclk_with(self.timpre)
+    }
+
+    pub const fn timclk2(&self) -> TimClk2 {
+        self.pclk2.to_timclk_with(self.timpre)
+    }
+}
+
+/// A frequency formatted in the largest unit that represents it exactly.
+pub(crate) struct Hz(pub(crate) u32);
+
+impl fmt::Display for Hz {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 % 1_000_000 == 0 {
+            write!(f, "{} MHz", self.0 / 1_000_000)
+        } else if self.0 % 1_000 == 0 {
+            write!(f, "{} kHz", self.0 / 1_000)
+        } else {
+            write!(f, "{} Hz", self.0)
+        }
+    }
+}
+
+impl fmt::Display for PllSrcMuxSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PllSrcMuxSignal::Hsi(clk) => write!(f, "HSI {}", Hz(clk.f())),
+            PllSrcMuxSignal::Hse(clk) => write!(f, "HSE {}", Hz(clk.f())),
+        }
+    }
+}
+
+impl fmt::Display for Pll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PLL: {} / {} * {} = VCO {}, P = VCO / {} = {}, Q = VCO / {} = {}",
+            self.vco.src.mux,
+            self.vco.src.m,
+            self.vco.n,
+            Hz(self.vco.f()),
+            self.p.div,
+            Hz(self.p.f()),
+            self.q.div,
+            Hz(self.q.f()),
+        )
+    }
+}
+
+impl fmt::Display for SysClkMuxSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SysClkMuxSignal::Hsi(_) => write!(f, "HSI"),
+            SysClkMuxSignal::Hse(_) => write!(f, "HSE"),
+            SysClkMuxSignal::Pll(_) => write!(f, "PLL_P"),
+        }
+    }
+}
+
+impl fmt::Display for SysClk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SYSCLK: {} = {}", self.mux, Hz(self.f()))?;
+        if self.overdrive {
+            write!(f, " with over-drive")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for HClk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HCLK: SYSCLK / {} = {}", self.hpre, Hz(self.f()))
+    }
+}
+
+impl fmt::Display for PClk1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PCLK1: HCLK / {} = {}", self.ppre1, Hz(self.f()))
+    }
+}
+
+impl fmt::Display for PClk2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PCLK2: HCLK / {} = {}", self.ppre2, Hz(self.f()))
+    }
+}
+
+impl fmt::Display for SysTickClk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SYSTICKCLK: HCLK / 8 = {}", Hz(self.f()))
+    }
+}
+
+impl fmt::Display for VoltageScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(not(stm32_mcu = "stm32f401"))]
+            VoltageScale::Scale1 => write!(f, "scale 1"),
+            VoltageScale::Scale2 => write!(f, "scale 2"),
+            #[cfg(not(any(
+                stm32_mcu = "stm32f405",
+                stm32_mcu = "stm32f407",
+                stm32_mcu = "stm32f415",
+                stm32_mcu = "stm32f417",
+            )))]
+            VoltageScale::Scale3 => write!(f, "scale 3"),
+        }
+    }
+}
+
+impl fmt::Display for ClockTree {
+    /// Format the clock tree with one node per line, followed by the flash latency and the voltage settings.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(pll) = self.pll {
+            writeln!(f, "{}", pll)?;
+        }
+        writeln!(f, "{}", self.sysclk())?;
+        writeln!(f, "{}", self.hclk())?;
+        writeln!(f, "{}", self.pclk1)?;
+        writeln!(f, "{}", self.pclk2)?;
+        writeln!(f, "{}", self.hclk().to_systickclk())?;
+        writeln!(
+            f,
+            "FLASH: {} wait states at {}",
+            self.hclk().get_wait_states(self.voltage),
+            self.voltage
+        )?;
+        write!(f, "VOS: {}", self.vos)
+    }
+}
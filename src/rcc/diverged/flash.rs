@@ -0,0 +1,61 @@
+use crate::periph::FlashPeriph;
+use drone_cortexm::reg::prelude::*;
+use drone_stm32_map::reg;
+
+#[allow(dead_code)]
+pub(crate) struct FlashDiverged {
+    pub(crate) flash_acr: reg::flash::Acr<Srt>,
+    pub(crate) flash_keyr: reg::flash::Keyr<Srt>,
+    pub(crate) flash_optkeyr: reg::flash::Optkeyr<Srt>,
+    pub(crate) flash_sr: reg::flash::Sr<Crt>,
+    pub(crate) flash_cr: reg::flash::Cr<Srt>,
+    pub(crate) flash_optcr: reg::flash::Optcr<Srt>,
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    pub(crate) flash_optcr1: reg::flash::Optcr1<Srt>,
+}
+
+impl From<FlashPeriph> for FlashDiverged {
+    fn from(periph: FlashPeriph) -> Self {
+        let FlashPeriph {
+            flash_acr,
+            flash_keyr,
+            flash_optkeyr,
+            flash_sr,
+            flash_cr,
+            flash_optcr,
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            flash_optcr1,
+        } = periph;
+        Self {
+            flash_acr,
+            flash_keyr,
+            flash_optkeyr,
+            flash_sr: flash_sr.into_copy(),
+            flash_cr,
+            flash_optcr,
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            flash_optcr1,
+        }
+    }
+}
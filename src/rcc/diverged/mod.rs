@@ -1,3 +1,5 @@
+mod flash;
+mod pwr;
 mod rcc;
 
-pub(crate) use self::rcc::RccDiverged;
+pub(crate) use self::{flash::FlashDiverged, pwr::PwrDiverged, rcc::RccDiverged};
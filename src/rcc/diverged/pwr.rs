@@ -0,0 +1,25 @@
+use crate::periph::PwrPeriph;
+use drone_cortexm::reg::prelude::*;
+use drone_stm32_map::reg;
+
+#[allow(dead_code)]
+pub(crate) struct PwrDiverged {
+    pub(crate) rcc_apb1enr_pwren: reg::rcc::apb1enr::Pwren<Srt>,
+    pub(crate) pwr_cr: reg::pwr::Cr<Srt>,
+    pub(crate) pwr_csr: reg::pwr::Csr<Crt>,
+}
+
+impl From<PwrPeriph> for PwrDiverged {
+    fn from(periph: PwrPeriph) -> Self {
+        let PwrPeriph {
+            rcc_apb1enr_pwren,
+            pwr_cr,
+            pwr_csr,
+        } = periph;
+        Self {
+            rcc_apb1enr_pwren,
+            pwr_cr,
+            pwr_csr: pwr_csr.into_copy(),
+        }
+    }
+}
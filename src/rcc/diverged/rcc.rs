@@ -4,10 +4,39 @@ use drone_stm32_map::reg;
 
 #[allow(dead_code)]
 pub(crate) struct RccDiverged {
-    pub(crate) rcc_cr: reg::rcc::Cr<Srt>,
+    pub(crate) rcc_cr: reg::rcc::Cr<Crt>,
     pub(crate) rcc_pllcfgr: reg::rcc::Pllcfgr<Srt>,
-    pub(crate) rcc_cfgr: reg::rcc::Cfgr<Srt>,
+    pub(crate) rcc_cfgr: reg::rcc::Cfgr<Crt>,
     pub(crate) rcc_cir: reg::rcc::Cir<Crt>,
+    pub(crate) rcc_bdcr: reg::rcc::Bdcr<Srt>,
+    pub(crate) rcc_csr: reg::rcc::Csr<Srt>,
+    #[cfg(not(any(
+        stm32_mcu = "stm32f405",
+        stm32_mcu = "stm32f407",
+        stm32_mcu = "stm32f415",
+        stm32_mcu = "stm32f417",
+    )))]
+    pub(crate) rcc_dckcfgr: reg::rcc::Dckcfgr<Srt>,
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f446",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    pub(crate) rcc_plli2scfgr: reg::rcc::Plli2Scfgr<Srt>,
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f446",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    pub(crate) rcc_pllsaicfgr: reg::rcc::Pllsaicfgr<Srt>,
 }
 
 impl From<RccPeriph> for RccDiverged {
@@ -17,12 +46,70 @@ impl From<RccPeriph> for RccDiverged {
             rcc_pllcfgr,
             rcc_cfgr,
             rcc_cir,
+            rcc_bdcr,
+            rcc_csr,
+            #[cfg(not(any(
+                stm32_mcu = "stm32f405",
+                stm32_mcu = "stm32f407",
+                stm32_mcu = "stm32f415",
+                stm32_mcu = "stm32f417",
+            )))]
+            rcc_dckcfgr,
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f446",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            rcc_plli2scfgr,
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f446",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            rcc_pllsaicfgr,
         } = periph;
         Self {
-            rcc_cr,
+            rcc_cr: rcc_cr.into_copy(),
             rcc_pllcfgr,
-            rcc_cfgr,
+            rcc_cfgr: rcc_cfgr.into_copy(),
             rcc_cir: rcc_cir.into_copy(),
+            rcc_bdcr,
+            rcc_csr,
+            #[cfg(not(any(
+                stm32_mcu = "stm32f405",
+                stm32_mcu = "stm32f407",
+                stm32_mcu = "stm32f415",
+                stm32_mcu = "stm32f417",
+            )))]
+            rcc_dckcfgr,
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f446",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            rcc_plli2scfgr,
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f446",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            rcc_pllsaicfgr,
         }
     }
 }
@@ -1,19 +1,90 @@
-use crate::{traits::*, clktree::Freq, clktree::HClk, periph::FlashPeriph};
+use crate::{traits::*, clktree::Freq, clktree::HClk, diverged::FlashDiverged, periph::FlashPeriph};
 use drone_cortexm::reg::prelude::*;
 
 pub struct Flash {
-    flash: FlashPeriph,
+    pub(crate) flash: FlashDiverged,
 }
 
 impl Flash {
     pub fn new(flash: FlashPeriph) -> Self {
-        Self { flash }
+        Self {
+            flash: flash.into(),
+        }
     }
 
+    /// Set the number of flash wait states.
+    /// The instruction and data caches are reset such that no line fetched with the previous latency is kept.
     pub fn set_latency(&self, wait_states: u32) {
+        let acr = self.flash.flash_acr.load();
         self.flash
             .flash_acr
-            .modify(|r| r.write_latency(wait_states));
+            .modify(|r| r.clear_icen().clear_dcen().write_latency(wait_states));
+        self.reset_caches();
+        self.flash.flash_acr.modify(|r| {
+            if acr.icen() {
+                r.set_icen();
+            }
+            if acr.dcen() {
+                r.set_dcen();
+            }
+            r
+        });
+    }
+
+    /// Enable or disable the ART accelerator prefetch buffer.
+    pub fn set_prefetch(&self, enabled: bool) {
+        self.flash.flash_acr.modify(|r| {
+            if enabled {
+                r.set_prften()
+            } else {
+                r.clear_prften()
+            }
+        });
+    }
+
+    /// Enable or disable the ART accelerator instruction cache.
+    pub fn set_icache(&self, enabled: bool) {
+        self.flash.flash_acr.modify(|r| {
+            if enabled {
+                r.set_icen()
+            } else {
+                r.clear_icen()
+            }
+        });
+    }
+
+    /// Enable or disable the ART accelerator data cache.
+    pub fn set_dcache(&self, enabled: bool) {
+        self.flash.flash_acr.modify(|r| {
+            if enabled {
+                r.set_dcen()
+            } else {
+                r.clear_dcen()
+            }
+        });
+    }
+
+    /// Reset the instruction and data caches.
+    /// A cache can only be reset while it is disabled, so the caches that are enabled are left untouched.
+    pub fn reset_caches(&self) {
+        let acr = self.flash.flash_acr.load();
+        self.flash.flash_acr.modify(|r| {
+            if !acr.icen() {
+                r.set_icrst();
+            }
+            if !acr.dcen() {
+                r.set_dcrst();
+            }
+            r
+        });
+        self.flash
+            .flash_acr
+            .modify(|r| r.clear_icrst().clear_dcrst());
+    }
+
+    /// Get the current number of flash wait states.
+    pub fn latency(&self) -> u32 {
+        self.flash.flash_acr.load().latency()
     }
 }
 
@@ -37,15 +108,18 @@ impl HClkExt for HClk {
     }
 }
 
-// STM32F42xxx and STM32F43xxx
+// STM32F42xxx, STM32F43xxx, and STM32F469xx/79xx
 #[cfg(any(
     stm32_mcu = "stm32f427",
     stm32_mcu = "stm32f429",
     stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
 ))]
 impl HClkExt for HClk {
     fn get_wait_states(&self, voltage: VoltageRange) -> u32 {
-        // Table 11 in PM0090.
+        // Table 11 in PM0090 and table 6 in RM0386.
         let upper = match voltage {
             VoltageRange::HighVoltage => [30, 60, 90, 120, 150, 180].as_ref(),
             VoltageRange::MediumVoltage => [24, 48, 72, 96, 120, 144, 168, 180].as_ref(),
@@ -56,6 +130,57 @@ impl HClkExt for HClk {
     }
 }
 
+// STM32F401xB/C and STM32F401xD/E
+#[cfg(stm32_mcu = "stm32f401")]
+impl HClkExt for HClk {
+    fn get_wait_states(&self, voltage: VoltageRange) -> u32 {
+        // Table 6 in RM0368.
+        let upper = match voltage {
+            VoltageRange::HighVoltage => [30, 60, 84].as_ref(),
+            VoltageRange::MediumVoltage => [24, 48, 72, 84].as_ref(),
+            VoltageRange::LowVoltage => [18, 36, 54, 72, 84].as_ref(),
+            VoltageRange::UltraLowVoltage => [16, 32, 48, 64, 80, 84].as_ref(),
+        };
+        get_wait_states(self, upper)
+    }
+}
+
+// STM32F410, STM32F411xC/E, STM32F412, and STM32F413/423
+#[cfg(any(
+    stm32_mcu = "stm32f410",
+    stm32_mcu = "stm32f411",
+    stm32_mcu = "stm32f412",
+    stm32_mcu = "stm32f413",
+    stm32_mcu = "stm32f423",
+))]
+impl HClkExt for HClk {
+    fn get_wait_states(&self, voltage: VoltageRange) -> u32 {
+        // Table 5 in RM0383, RM0401, RM0402, and RM0430.
+        let upper = match voltage {
+            VoltageRange::HighVoltage => [30, 64, 90, 100].as_ref(),
+            VoltageRange::MediumVoltage => [24, 48, 72, 96, 100].as_ref(),
+            VoltageRange::LowVoltage => [18, 36, 54, 72, 90, 100].as_ref(),
+            VoltageRange::UltraLowVoltage => [16, 32, 48, 64, 80, 96, 100].as_ref(),
+        };
+        get_wait_states(self, upper)
+    }
+}
+
+// STM32F446xx
+#[cfg(stm32_mcu = "stm32f446")]
+impl HClkExt for HClk {
+    fn get_wait_states(&self, voltage: VoltageRange) -> u32 {
+        // Table 5 in RM0390.
+        let upper = match voltage {
+            VoltageRange::HighVoltage => [30, 60, 90, 120, 150, 180].as_ref(),
+            VoltageRange::MediumVoltage => [24, 48, 72, 96, 120, 144, 168, 180].as_ref(),
+            VoltageRange::LowVoltage => [22, 44, 66, 88, 110, 132, 154, 176, 180].as_ref(),
+            VoltageRange::UltraLowVoltage => [20, 40, 60, 80, 100, 120, 140, 160, 168].as_ref(),
+        };
+        get_wait_states(self, upper)
+    }
+}
+
 fn get_wait_states(hclk: &HClk, upper: &[u32]) -> u32 {
     let hclk = hclk.freq() / 1_000_000;
     upper
@@ -0,0 +1,342 @@
+use crate::{signature::flash_size_kb, traits::VoltageRange, Flash};
+use core::ptr::write_volatile;
+use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::reg;
+use fib::FiberFuture;
+
+const KEY1: u32 = 0x4567_0123;
+const KEY2: u32 = 0xCDEF_89AB;
+
+/// Base address of the main flash memory.
+const FLASH_BASE: usize = 0x0800_0000;
+
+/// Sector number offset of the second bank, i.e. the second bank starts at sector 12.
+const BANK2_SECTOR: u32 = 12;
+
+/// Flash erase and program error.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FlashError {
+    /// Write protection error (WRPERR).
+    WriteProtection,
+    /// Programming alignment error (PGAERR).
+    Alignment,
+    /// Programming parallelism error (PGPERR).
+    Parallelism,
+    /// Programming sequence error (PGSERR).
+    Sequence,
+    /// Operation error (OPERR).
+    Operation,
+    /// Read protection error (RDERR), i.e. a read from a proprietary code readout protected sector.
+    #[cfg(not(any(
+        stm32_mcu = "stm32f405",
+        stm32_mcu = "stm32f407",
+        stm32_mcu = "stm32f415",
+        stm32_mcu = "stm32f417",
+    )))]
+    ReadProtection,
+}
+
+/// A flash memory sector.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FlashSector {
+    /// The sector number.
+    pub number: u32,
+    /// The sector start address.
+    pub address: usize,
+    /// The sector size in bytes.
+    pub size: usize,
+    snb: u32,
+}
+
+impl FlashSector {
+    /// Get the sector with a given number, or `None` if it does not exist on the device.
+    /// The sectors in the second bank of a dual bank device are numbered from 12.
+    pub fn new(flash: &Flash, number: u32) -> Option<FlashSector> {
+        let (banks, bank_kb) = bank_layout(flash);
+        let bank_sectors = bank_sectors(bank_kb);
+        let (bank, n) = if banks == 2 {
+            (number / BANK2_SECTOR, number % BANK2_SECTOR)
+        } else {
+            (0, number)
+        };
+        if bank >= banks || n >= bank_sectors {
+            return None;
+        }
+        let (offset_kb, size_kb) = match n {
+            0..=3 => (n * 16, 16),
+            4 => (64, 64),
+            _ => ((n - 4) * 128, 128),
+        };
+        Some(FlashSector {
+            number,
+            address: FLASH_BASE + ((bank * bank_kb + offset_kb) as usize) * 1024,
+            size: (size_kb as usize) * 1024,
+            snb: bank << 4 | n,
+        })
+    }
+
+    /// Get the sector that contains a given address, or `None` if the address is outside the main flash memory.
+    pub fn containing(flash: &Flash, address: usize) -> Option<FlashSector> {
+        (0..2 * BANK2_SECTOR)
+            .filter_map(|number| FlashSector::new(flash, number))
+            .find(|sector| address >= sector.address && address < sector.address + sector.size)
+    }
+}
+
+/// Get the number of banks and the size of each bank in kilobytes.
+#[allow(unused_variables)]
+fn bank_layout(flash: &Flash) -> (u32, u32) {
+    let size_kb = flash_size_kb();
+    // The 2 Mbyte devices are organized in two banks of 1 Mbyte each,
+    // and the 1 Mbyte devices in two banks of 512 Kbyte each when the DB1M option bit is set.
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    let layout = if size_kb == 2048 {
+        (2, 1024)
+    } else if size_kb == 1024 && flash.flash.flash_optcr.load().db1m() {
+        (2, 512)
+    } else {
+        (1, size_kb)
+    };
+    #[cfg(not(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    )))]
+    let layout = (1, size_kb);
+    layout
+}
+
+/// Get the number of sectors in a bank, i.e. four 16 Kbyte sectors,
+/// one 64 Kbyte sector, and 128 Kbyte sectors for the remaining space.
+fn bank_sectors(bank_kb: u32) -> u32 {
+    if bank_kb <= 64 {
+        bank_kb / 16
+    } else {
+        5 + (bank_kb - 128) / 128
+    }
+}
+
+/// Get the program/erase parallelism (PSIZE) and the corresponding number of bytes for a voltage range.
+fn parallelism(voltage: VoltageRange) -> (u32, usize) {
+    // Table 7 in RM0090.
+    match voltage {
+        VoltageRange::HighVoltage => (0b10, 4),
+        VoltageRange::MediumVoltage | VoltageRange::LowVoltage => (0b01, 2),
+        VoltageRange::UltraLowVoltage => (0b00, 1),
+    }
+}
+
+/// Flash erase and program driver.
+/// The flash control register is locked when the driver is dropped.
+pub struct FlashProg<'a, FlashInt: IntToken> {
+    flash: &'a Flash,
+    flash_int: FlashInt,
+    voltage: VoltageRange,
+}
+
+impl Flash {
+    /// Unlock the flash control register for erase and program operations
+    /// at the parallelism supported by the `voltage` range.
+    pub fn unlock<FlashInt: IntToken>(
+        &self,
+        flash_int: FlashInt,
+        voltage: VoltageRange,
+    ) -> FlashProg<'_, FlashInt> {
+        if self.flash.flash_cr.load().lock() {
+            self.flash.flash_keyr.store(|r| r.write_key(KEY1));
+            self.flash.flash_keyr.store(|r| r.write_key(KEY2));
+        }
+        self.flash.flash_cr.modify(|r| r.set_eopie().set_errie());
+        FlashProg {
+            flash: self,
+            flash_int,
+            voltage,
+        }
+    }
+}
+
+impl<FlashInt: IntToken> FlashProg<'_, FlashInt> {
+    /// Erase a sector.
+    pub async fn erase_sector(&mut self, sector: FlashSector) -> Result<(), FlashError> {
+        let (psize, _) = parallelism(self.voltage);
+        let flash_cr = &self.flash.flash.flash_cr;
+        self.clear_errors();
+        flash_cr.modify(|r| r.set_ser().write_snb(sector.snb).write_psize(psize));
+        let result = self.start().await;
+        flash_cr.modify(|r| r.clear_ser());
+        result
+    }
+
+    /// Erase the entire main flash memory.
+    /// The program must be executing from ram.
+    pub async fn erase_all(&mut self) -> Result<(), FlashError> {
+        let (psize, _) = parallelism(self.voltage);
+        let flash_cr = &self.flash.flash.flash_cr;
+        self.clear_errors();
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        flash_cr.modify(|r| r.set_mer().set_mer1().write_psize(psize));
+        #[cfg(not(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        )))]
+        flash_cr.modify(|r| r.set_mer().write_psize(psize));
+        let result = self.start().await;
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        flash_cr.modify(|r| r.clear_mer().clear_mer1());
+        #[cfg(not(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        )))]
+        flash_cr.modify(|r| r.clear_mer());
+        result
+    }
+
+    /// Program `data` to the flash memory starting at `address`.
+    /// The target must be erased, and both `address` and the length of `data`
+    /// must be a multiple of the program parallelism.
+    pub async fn program(&mut self, address: usize, data: &[u8]) -> Result<(), FlashError> {
+        let (psize, width) = parallelism(self.voltage);
+        assert_eq!(address % width, 0);
+        assert_eq!(data.len() % width, 0);
+        let flash_cr = &self.flash.flash.flash_cr;
+        self.clear_errors();
+        flash_cr.modify(|r| r.set_pg().write_psize(psize));
+        let mut result = Ok(());
+        for (i, chunk) in data.chunks_exact(width).enumerate() {
+            let done = self.wait();
+            let dst = address + i * width;
+            unsafe {
+                match width {
+                    1 => write_volatile(dst as *mut u8, chunk[0]),
+                    2 => write_volatile(dst as *mut u16, u16::from_le_bytes([chunk[0], chunk[1]])),
+                    _ => write_volatile(
+                        dst as *mut u32,
+                        u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+                    ),
+                }
+            }
+            result = done.await;
+            if result.is_err() {
+                break;
+            }
+        }
+        flash_cr.modify(|r| r.clear_pg());
+        result
+    }
+
+    fn start(&self) -> FiberFuture<Result<(), FlashError>> {
+        let done = self.wait();
+        self.flash.flash.flash_cr.modify(|r| r.set_strt());
+        done
+    }
+
+    fn wait(&self) -> FiberFuture<Result<(), FlashError>> {
+        let flash_sr = self.flash.flash.flash_sr;
+        self.flash_int.add_future(fib::new_fn(move || {
+            if let Some(error) = take_error(flash_sr) {
+                fib::Complete(Err(error))
+            } else if flash_sr.load().eop() {
+                flash_sr.store(|r| r.set_eop());
+                fib::Complete(Ok(()))
+            } else {
+                fib::Yielded(())
+            }
+        }))
+    }
+
+    /// Clear the error flags left by a previous operation.
+    fn clear_errors(&self) {
+        let flash_sr = self.flash.flash.flash_sr;
+        take_error(flash_sr);
+        flash_sr.store(|r| r.set_eop());
+    }
+}
+
+/// Get the error flagged in the status register, if any, and clear the error flags.
+pub(crate) fn take_error(flash_sr: reg::flash::Sr<Crt>) -> Option<FlashError> {
+    let sr = flash_sr.load();
+    let error = if sr.wrperr() {
+        Some(FlashError::WriteProtection)
+    } else if sr.pgaerr() {
+        Some(FlashError::Alignment)
+    } else if sr.pgperr() {
+        Some(FlashError::Parallelism)
+    } else if sr.pgserr() {
+        Some(FlashError::Sequence)
+    } else if sr.operr() {
+        Some(FlashError::Operation)
+    } else {
+        None
+    };
+    #[cfg(not(any(
+        stm32_mcu = "stm32f405",
+        stm32_mcu = "stm32f407",
+        stm32_mcu = "stm32f415",
+        stm32_mcu = "stm32f417",
+    )))]
+    let error = error.or_else(|| {
+        if sr.rderr() {
+            Some(FlashError::ReadProtection)
+        } else {
+            None
+        }
+    });
+    if error.is_some() {
+        flash_sr.store(|r| {
+            #[cfg(not(any(
+                stm32_mcu = "stm32f405",
+                stm32_mcu = "stm32f407",
+                stm32_mcu = "stm32f415",
+                stm32_mcu = "stm32f417",
+            )))]
+            r.set_rderr();
+            r.set_operr()
+                .set_wrperr()
+                .set_pgaerr()
+                .set_pgperr()
+                .set_pgserr()
+        });
+    }
+    error
+}
+
+impl<FlashInt: IntToken> Drop for FlashProg<'_, FlashInt> {
+    fn drop(&mut self) {
+        self.flash
+            .flash
+            .flash_cr
+            .modify(|r| r.clear_eopie().clear_errie().set_lock());
+    }
+}
@@ -1,20 +1,47 @@
 #![feature(proc_macro_hygiene)]
 #![cfg_attr(not(feature = "std"), no_std)]
 
+mod backup;
 pub mod clktree;
 mod diverged;
 mod flash;
+mod flash_prog;
+mod mco;
+mod option_bytes;
 mod periph;
+mod pvd;
 mod pwr;
 mod rcc;
+mod report;
+mod reset;
+pub mod signature;
 mod traits;
 
+extern crate alloc;
+
+pub use self::backup::{BackupDomain, BackupReg, BACKUP_REG_COUNT};
+#[cfg(not(any(
+    stm32_mcu = "stm32f401",
+    stm32_mcu = "stm32f410",
+    stm32_mcu = "stm32f411",
+    stm32_mcu = "stm32f412",
+    stm32_mcu = "stm32f413",
+    stm32_mcu = "stm32f423",
+)))]
+pub use self::backup::BKPSRAM_SIZE;
 pub use self::flash::Flash;
+pub use self::flash_prog::{FlashError, FlashProg, FlashSector};
+pub use self::mco::{Defined, Mco1, Mco1Signal, Mco2, Mco2Signal, McoMap, McoPins, McoPre, Undefined, MCO_MAX};
+pub use self::option_bytes::{BorLevel, Level2Confirmation, MassEraseConfirmation, OptionBytes, ReadProtection};
 pub use self::periph::*;
-pub use self::pwr::Pwr;
-pub use self::rcc::{Rcc, RccSetup};
+pub use self::pvd::{Pvd, PvdEvent, PvdLevel};
+pub use self::pwr::{BackupDomainAccess, Pwr, StopMode, StopRegulator, WakeupPin};
+pub use self::rcc::{ConfiguredClockTree, CssPolicy, HseFailure, Rcc, RccSetup};
+pub use self::report::{ClockTreeComparison, ClockTreeSettings, PllSettings};
+pub use self::reset::ResetCause;
 pub use self::prelude::*;
 
 pub mod prelude {
+    pub use crate::mco::traits::*;
     pub use crate::traits::*;
 }
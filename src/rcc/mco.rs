@@ -0,0 +1,198 @@
+use crate::{clktree::*, traits::ConfiguredClk, Rcc};
+use core::marker::PhantomData;
+use drone_cortexm::{reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::periph::gpio::pin::{GpioA8, GpioC9};
+use drone_stm32f4_gpio_drv::{pin_impl, prelude::*};
+
+/// Maximum microcontroller clock output frequency.
+pub const MCO_MAX: u32 = 100_000_000;
+
+pub trait McoMap {}
+
+/// The microcontroller clock output 1 (MCO1).
+pub struct Mco1;
+/// The microcontroller clock output 2 (MCO2).
+pub struct Mco2;
+
+impl McoMap for Mco1 {}
+impl McoMap for Mco2 {}
+
+pub struct Defined;
+pub struct Undefined;
+
+pub struct McoPins<Mco: McoMap, Out> {
+    mco: PhantomData<Mco>,
+    out: PhantomData<Out>,
+}
+
+impl<Mco: McoMap, Out> McoPins<Mco, Out> {
+    pub fn new() -> Self {
+        Self {
+            mco: PhantomData,
+            out: PhantomData,
+        }
+    }
+}
+
+impl<Mco: McoMap> Default for McoPins<Mco, Undefined> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub mod traits {
+    use super::*;
+    use drone_stm32f4_gpio_drv::pin_ext;
+
+    pin_ext!(OutPinExt<Mco: McoMap, ..., Out>.out -> McoPins<Mco, Defined>);
+}
+
+use self::traits::OutPinExt;
+
+pin_impl!(OutPinExt for McoPins<Mco1, ...>.out, GpioA8, AlternateMode<PinAf0>; Undefined -> Defined);
+pin_impl!(OutPinExt for McoPins<Mco2, ...>.out, GpioC9, AlternateMode<PinAf0>; Undefined -> Defined);
+
+/// The MCO1 clock source signal.
+#[derive(Copy, Clone)]
+pub enum Mco1Signal {
+    Hsi(HsiClk),
+    Lse(ConfiguredClk<LseClk>),
+    Hse(ConfiguredClk<HseClk>),
+    Pll(ConfiguredClk<PllClk<PllP>>),
+}
+
+impl Mco1Signal {
+    pub fn f(&self) -> u32 {
+        match self {
+            Mco1Signal::Hsi(clk) => clk.f(),
+            Mco1Signal::Lse(clk) => clk.f(),
+            Mco1Signal::Hse(clk) => clk.f(),
+            Mco1Signal::Pll(clk) => clk.f(),
+        }
+    }
+}
+
+/// The MCO2 clock source signal.
+#[derive(Copy, Clone)]
+pub enum Mco2Signal {
+    SysClk(ConfiguredClk<SysClk>),
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f446",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    PllI2s(ConfiguredClk<PllClk<PllI2sR>>),
+    Hse(ConfiguredClk<HseClk>),
+    Pll(ConfiguredClk<PllClk<PllP>>),
+}
+
+impl Mco2Signal {
+    pub fn f(&self) -> u32 {
+        match self {
+            Mco2Signal::SysClk(clk) => clk.f(),
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f446",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            Mco2Signal::PllI2s(clk) => clk.f(),
+            Mco2Signal::Hse(clk) => clk.f(),
+            Mco2Signal::Pll(clk) => clk.f(),
+        }
+    }
+}
+
+/// The microcontroller clock output prescaler.
+#[derive(Copy, Clone)]
+pub enum McoPre {
+    Div1,
+    Div2,
+    Div3,
+    Div4,
+    Div5,
+}
+
+impl McoPre {
+    fn div(&self) -> u32 {
+        match self {
+            McoPre::Div1 => 1,
+            McoPre::Div2 => 2,
+            McoPre::Div3 => 3,
+            McoPre::Div4 => 4,
+            McoPre::Div5 => 5,
+        }
+    }
+
+    fn bits(&self) -> u32 {
+        match self {
+            McoPre::Div1 => 0b000,
+            McoPre::Div2 => 0b100,
+            McoPre::Div3 => 0b101,
+            McoPre::Div4 => 0b110,
+            McoPre::Div5 => 0b111,
+        }
+    }
+}
+
+impl<RccInt: IntToken> Rcc<RccInt> {
+    /// Output a clock signal on MCO1 (PA8).
+    /// Returns the output frequency.
+    pub fn enable_mco1(
+        &self,
+        signal: Mco1Signal,
+        pre: McoPre,
+        _pins: &McoPins<Mco1, Defined>,
+    ) -> u32 {
+        let f = signal.f() / pre.div();
+        assert!(f <= MCO_MAX);
+        let mco1 = match signal {
+            Mco1Signal::Hsi(_) => 0b00,
+            Mco1Signal::Lse(_) => 0b01,
+            Mco1Signal::Hse(_) => 0b10,
+            Mco1Signal::Pll(_) => 0b11,
+        };
+        self.rcc
+            .rcc_cfgr
+            .modify(|r| r.write_mco1(mco1).write_mco1pre(pre.bits()));
+        f
+    }
+
+    /// Output a clock signal on MCO2 (PC9).
+    /// Returns the output frequency.
+    pub fn enable_mco2(
+        &self,
+        signal: Mco2Signal,
+        pre: McoPre,
+        _pins: &McoPins<Mco2, Defined>,
+    ) -> u32 {
+        let f = signal.f() / pre.div();
+        assert!(f <= MCO_MAX);
+        let mco2 = match signal {
+            Mco2Signal::SysClk(_) => 0b00,
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f446",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            Mco2Signal::PllI2s(_) => 0b01,
+            Mco2Signal::Hse(_) => 0b10,
+            Mco2Signal::Pll(_) => 0b11,
+        };
+        self.rcc
+            .rcc_cfgr
+            .modify(|r| r.write_mco2(mco2).write_mco2pre(pre.bits()));
+        f
+    }
+}
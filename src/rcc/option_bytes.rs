@@ -0,0 +1,237 @@
+use crate::{flash_prog::take_error, Flash, FlashError};
+use drone_cortexm::reg::prelude::*;
+
+const OPTKEY1: u32 = 0x0819_2A3B;
+const OPTKEY2: u32 = 0x4C5D_6E7F;
+
+/// The RDP option byte values for level 0 and level 2. Any other value is level 1.
+const RDP_LEVEL0: u32 = 0xAA;
+const RDP_LEVEL1: u32 = 0x55;
+const RDP_LEVEL2: u32 = 0xCC;
+
+/// Number of sectors covered by the nWRP bits of each bank.
+const WRP_SECTORS: u32 = 12;
+
+/// A confirmation that read protection level 2 is requested deliberately.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Level2Confirmation(());
+
+impl Level2Confirmation {
+    /// Confirm read protection level 2.
+    ///
+    /// # Safety
+    ///
+    /// Level 2 is irreversible. The debug interface, the boot from ram or system memory,
+    /// and any further change of the option bytes are permanently disabled.
+    pub const unsafe fn new() -> Self {
+        Level2Confirmation(())
+    }
+}
+
+/// A confirmation that the mass erase caused by going from read protection level 1 to level 0 is requested deliberately.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MassEraseConfirmation(());
+
+impl MassEraseConfirmation {
+    /// Confirm the mass erase.
+    ///
+    /// # Safety
+    ///
+    /// The entire main flash memory is erased, including the program that is running from it,
+    /// so the program must execute from ram.
+    pub const unsafe fn new() -> Self {
+        MassEraseConfirmation(())
+    }
+}
+
+/// The flash read protection level.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ReadProtection {
+    /// No protection.
+    Level0,
+    /// The flash memory is not readable by the debug interface or when booting from ram or system memory.
+    /// Going back to level 0 mass erases the flash memory.
+    Level1,
+    /// The chip is permanently protected.
+    Level2(Level2Confirmation),
+}
+
+/// The brownout reset threshold level.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BorLevel {
+    /// Reset threshold of approximately 2.70V-3.60V.
+    Level3,
+    /// Reset threshold of approximately 2.40V-2.70V.
+    Level2,
+    /// Reset threshold of approximately 2.10V-2.40V.
+    Level1,
+    /// The brownout reset is off, i.e. the reset threshold is 1.8V-2.10V.
+    Off,
+}
+
+/// The user configurable flash option bytes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OptionBytes {
+    /// The read protection level.
+    pub rdp: ReadProtection,
+    /// The brownout reset level.
+    pub bor: BorLevel,
+    /// Software watchdog (`true`) or hardware watchdog (`false`), i.e. WDG_SW.
+    pub wdg_sw: bool,
+    /// No reset generated when entering Stop mode, i.e. nRST_STOP.
+    pub nrst_stop: bool,
+    /// No reset generated when entering Standby mode, i.e. nRST_STDBY.
+    pub nrst_stdby: bool,
+    /// The write protected sectors with one bit per sector, where bit n set means that sector n is protected.
+    pub write_protected: u32,
+    /// Dual bank organization of the 1 Mbyte devices, i.e. DB1M.
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    pub dual_bank: bool,
+}
+
+impl Flash {
+    /// Read the current option bytes.
+    pub fn option_bytes(&self) -> OptionBytes {
+        let optcr = self.flash.flash_optcr.load();
+        let rdp = match optcr.rdp() {
+            RDP_LEVEL0 => ReadProtection::Level0,
+            RDP_LEVEL2 => ReadProtection::Level2(Level2Confirmation(())),
+            _ => ReadProtection::Level1,
+        };
+        let bor = match optcr.bor_lev() {
+            0b00 => BorLevel::Level3,
+            0b01 => BorLevel::Level2,
+            0b10 => BorLevel::Level1,
+            _ => BorLevel::Off,
+        };
+        let wrp_mask = (1 << WRP_SECTORS) - 1;
+        #[allow(unused_mut)]
+        let mut write_protected = !optcr.n_wrp() & wrp_mask;
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        {
+            write_protected |= (!self.flash.flash_optcr1.load().n_wrp() & wrp_mask) << WRP_SECTORS;
+        }
+        OptionBytes {
+            rdp,
+            bor,
+            wdg_sw: optcr.wdg_sw(),
+            nrst_stop: optcr.n_rst_stop(),
+            nrst_stdby: optcr.n_rst_stdby(),
+            write_protected,
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            dual_bank: optcr.db1m(),
+        }
+    }
+
+    /// Program the option bytes.
+    /// The new values take effect after the next system reset.
+    ///
+    /// Going from read protection level 1 to level 0 mass erases the flash memory,
+    /// and panics unless `mass_erase` is given.
+    pub fn program_option_bytes(
+        &self,
+        ob: OptionBytes,
+        mass_erase: Option<MassEraseConfirmation>,
+    ) -> Result<(), FlashError> {
+        if self.option_bytes().rdp == ReadProtection::Level1 && ob.rdp == ReadProtection::Level0 {
+            assert!(
+                mass_erase.is_some(),
+                "Confirm the mass erase before going from read protection level 1 to level 0."
+            );
+        }
+        let rdp = match ob.rdp {
+            ReadProtection::Level0 => RDP_LEVEL0,
+            ReadProtection::Level1 => RDP_LEVEL1,
+            ReadProtection::Level2(_) => RDP_LEVEL2,
+        };
+        let bor = match ob.bor {
+            BorLevel::Level3 => 0b00,
+            BorLevel::Level2 => 0b01,
+            BorLevel::Level1 => 0b10,
+            BorLevel::Off => 0b11,
+        };
+        let wrp_mask = (1 << WRP_SECTORS) - 1;
+
+        // Unlock the option control register.
+        if self.flash.flash_optcr.load().optlock() {
+            self.flash.flash_optkeyr.store(|r| r.write_optkey(OPTKEY1));
+            self.flash.flash_optkeyr.store(|r| r.write_optkey(OPTKEY2));
+        }
+        while self.flash.flash_sr.load().bsy() {}
+        take_error(self.flash.flash_sr);
+
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        self.flash
+            .flash_optcr1
+            .modify(|r| r.write_n_wrp(!(ob.write_protected >> WRP_SECTORS) & wrp_mask));
+        self.flash.flash_optcr.modify(|r| {
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            if ob.dual_bank {
+                r.set_db1m();
+            } else {
+                r.clear_db1m();
+            }
+            if ob.wdg_sw {
+                r.set_wdg_sw();
+            } else {
+                r.clear_wdg_sw();
+            }
+            if ob.nrst_stop {
+                r.set_n_rst_stop();
+            } else {
+                r.clear_n_rst_stop();
+            }
+            if ob.nrst_stdby {
+                r.set_n_rst_stdby();
+            } else {
+                r.clear_n_rst_stdby();
+            }
+            r.write_rdp(rdp)
+                .write_bor_lev(bor)
+                .write_n_wrp(!ob.write_protected & wrp_mask)
+        });
+
+        // Start the option byte modification and wait for it to complete.
+        self.flash.flash_optcr.modify(|r| r.set_optstrt());
+        while self.flash.flash_sr.load().bsy() {}
+        let error = take_error(self.flash.flash_sr);
+
+        self.flash.flash_optcr.modify(|r| r.set_optlock());
+        error.map_or(Ok(()), Err)
+    }
+}
@@ -15,6 +15,35 @@ periph::singular! {
         PLLCFGR;
         CFGR;
         CIR;
+        BDCR;
+        CSR;
+        #[cfg(not(any(
+            stm32_mcu = "stm32f405",
+            stm32_mcu = "stm32f407",
+            stm32_mcu = "stm32f415",
+            stm32_mcu = "stm32f417",
+        )))]
+        DCKCFGR;
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f446",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        PLLI2SCFGR;
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f446",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        PLLSAICFGR;
     }
 }
 
@@ -51,5 +80,92 @@ periph::singular! {
 
     FLASH {
         ACR;
+        KEYR;
+        OPTKEYR;
+        SR;
+        CR;
+        OPTCR;
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        OPTCR1;
+    }
+}
+
+periph::singular! {
+    /// Extracts PVD register tokens.
+    pub macro periph_pvd;
+    /// PVD peripheral, i.e. the EXTI line 16.
+    pub struct PvdPeriph;
+    // Path prefix to reach registers.
+    drone_stm32_map::reg;
+    // Absolute path to the current module.
+    crate;
+
+    EXTI {
+        IMR {
+            MR16;
+        }
+        RTSR {
+            TR16;
+        }
+        FTSR {
+            TR16;
+        }
+        PR {
+            PR16;
+        }
+    }
+}
+
+periph::singular! {
+    /// Extracts backup domain register tokens.
+    pub macro periph_backup;
+    /// Backup domain peripheral, i.e. the RTC backup registers and the backup sram clock.
+    pub struct BackupPeriph;
+    // Path prefix to reach registers.
+    drone_stm32_map::reg;
+    // Absolute path to the current module.
+    crate;
+
+    RCC {
+        #[cfg(not(any(
+            stm32_mcu = "stm32f401",
+            stm32_mcu = "stm32f410",
+            stm32_mcu = "stm32f411",
+            stm32_mcu = "stm32f412",
+            stm32_mcu = "stm32f413",
+            stm32_mcu = "stm32f423",
+        )))]
+        AHB1ENR {
+            BKPSRAMEN;
+        }
+    }
+    RTC {
+        BKP0R;
+        BKP1R;
+        BKP2R;
+        BKP3R;
+        BKP4R;
+        BKP5R;
+        BKP6R;
+        BKP7R;
+        BKP8R;
+        BKP9R;
+        BKP10R;
+        BKP11R;
+        BKP12R;
+        BKP13R;
+        BKP14R;
+        BKP15R;
+        BKP16R;
+        BKP17R;
+        BKP18R;
+        BKP19R;
     }
 }
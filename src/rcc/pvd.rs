@@ -0,0 +1,130 @@
+use crate::{periph::PvdPeriph, Pwr};
+use drone_core::fib::FiberStreamRing;
+use drone_cortexm::{fib, fib::Fiber, reg::prelude::*, thr::prelude::*};
+use drone_stm32_map::reg;
+
+/// The programmable voltage detector (PVD) threshold.
+#[derive(Copy, Clone)]
+pub enum PvdLevel {
+    /// 2.0V.
+    V2_0,
+    /// 2.1V.
+    V2_1,
+    /// 2.3V.
+    V2_3,
+    /// 2.5V.
+    V2_5,
+    /// 2.6V.
+    V2_6,
+    /// 2.7V.
+    V2_7,
+    /// 2.8V.
+    V2_8,
+    /// 2.9V.
+    V2_9,
+}
+
+/// A supply voltage transition detected by the PVD.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PvdEvent {
+    /// VDD dropped below the threshold.
+    BelowThreshold,
+    /// VDD rose above the threshold.
+    AboveThreshold,
+}
+
+/// Programmable voltage detector driver.
+/// The detector stays enabled until it is disabled by `disable()`.
+pub struct Pvd<PvdInt: IntToken> {
+    exti_imr_mr16: reg::exti::imr::Mr16<Srt>,
+    exti_rtsr_tr16: reg::exti::rtsr::Tr16<Srt>,
+    exti_ftsr_tr16: reg::exti::ftsr::Tr16<Srt>,
+    exti_pr_pr16: reg::exti::pr::Pr16<Crt>,
+    pwr_csr: reg::pwr::Csr<Crt>,
+    pvd_int: PvdInt,
+}
+
+impl<PvdInt: IntToken> Pvd<PvdInt> {
+    /// Enable the PVD with the given threshold.
+    /// `pvd_int` is the PVD interrupt, i.e. the EXTI line 16 interrupt.
+    #[must_use]
+    pub fn init(pvd: PvdPeriph, pvd_int: PvdInt, pwr: &Pwr, level: PvdLevel) -> Pvd<PvdInt> {
+        let PvdPeriph {
+            exti_imr_mr16,
+            exti_rtsr_tr16,
+            exti_ftsr_tr16,
+            exti_pr_pr16,
+        } = pvd;
+
+        // Set the threshold and enable the detector.
+        let pls = match level {
+            PvdLevel::V2_0 => 0b000,
+            PvdLevel::V2_1 => 0b001,
+            PvdLevel::V2_3 => 0b010,
+            PvdLevel::V2_5 => 0b011,
+            PvdLevel::V2_6 => 0b100,
+            PvdLevel::V2_7 => 0b101,
+            PvdLevel::V2_8 => 0b110,
+            PvdLevel::V2_9 => 0b111,
+        };
+        pwr.pwr.pwr_cr.modify(|r| r.write_pls(pls).set_pvde());
+
+        // Trigger on both the falling and rising supply voltage.
+        exti_rtsr_tr16.set_bit();
+        exti_ftsr_tr16.set_bit();
+        exti_imr_mr16.set_bit();
+
+        Pvd {
+            exti_imr_mr16,
+            exti_rtsr_tr16,
+            exti_ftsr_tr16,
+            exti_pr_pr16: exti_pr_pr16.into_copy(),
+            pwr_csr: pwr.pwr.pwr_csr,
+            pvd_int,
+        }
+    }
+
+    /// Disable the PVD and its EXTI line 16 interrupt.
+    pub fn disable(self, pwr: &Pwr) {
+        self.exti_imr_mr16.clear_bit();
+        self.exti_rtsr_tr16.clear_bit();
+        self.exti_ftsr_tr16.clear_bit();
+        pwr.pwr.pwr_cr.modify(|r| r.clear_pvde());
+
+        // Clear any pending flag.
+        self.exti_pr_pr16.set_bit();
+    }
+
+    /// Get whether VDD is currently below the threshold.
+    pub fn is_below_threshold(&self) -> bool {
+        self.pwr_csr.pvdo.read_bit()
+    }
+
+    /// Creates a new saturating stream of supply voltage transitions.
+    pub fn saturating_stream(&self, capacity: usize) -> FiberStreamRing<PvdEvent> {
+        self.pvd_int.add_saturating_stream(capacity, self.new_fib())
+    }
+
+    /// Creates a new overwriting stream of supply voltage transitions.
+    pub fn overwriting_stream(&self, capacity: usize) -> FiberStreamRing<PvdEvent> {
+        self.pvd_int.add_overwriting_stream(capacity, self.new_fib())
+    }
+
+    fn new_fib<R>(&self) -> impl Fiber<Input = (), Yield = Option<PvdEvent>, Return = R> {
+        let exti_pr_pr16 = self.exti_pr_pr16;
+        let pvdo = self.pwr_csr.pvdo;
+        fib::new_fn(move || {
+            if exti_pr_pr16.read_bit() {
+                // Clear pending flag.
+                exti_pr_pr16.set_bit();
+                if pvdo.read_bit() {
+                    fib::Yielded(Some(PvdEvent::BelowThreshold))
+                } else {
+                    fib::Yielded(Some(PvdEvent::AboveThreshold))
+                }
+            } else {
+                fib::Yielded(None)
+            }
+        })
+    }
+}
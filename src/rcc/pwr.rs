@@ -1,8 +1,22 @@
-use crate::{traits::*, periph::PwrPeriph};
-use drone_cortexm::reg::prelude::*;
+use crate::{traits::*, clktree::VoltageScale, diverged::PwrDiverged, periph::PwrPeriph};
+use core::future::Future;
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use drone_cortexm::{map::reg::scb, processor, reg::prelude::*};
 
 pub struct Pwr {
-    pwr: PwrPeriph,
+    pub(crate) pwr: PwrDiverged,
 }
 
 impl Pwr {
@@ -10,15 +24,311 @@ impl Pwr {
         // Enable pwr clock.
         pwr.rcc_apb1enr_pwren.set_bit();
 
-        Pwr { pwr }
+        Pwr { pwr: pwr.into() }
+    }
+
+    /// Enable write access to the backup domain,
+    /// i.e. the RTC registers, the RTC backup registers, the backup SRAM, and the RCC_BDCR register.
+    pub fn enable_backup_domain_access(&self) -> BackupDomainAccess {
+        self.pwr.pwr_cr.modify(|r| r.set_dbp());
+        BackupDomainAccess { _private: () }
+    }
+}
+
+/// The voltage regulator mode while in Stop mode.
+#[derive(Copy, Clone, PartialEq)]
+pub enum StopRegulator {
+    /// The main regulator is on, giving the fastest wake-up.
+    Main,
+    /// The low-power regulator is on, giving the lowest consumption.
+    LowPower,
+}
+
+/// Stop mode configuration.
+#[derive(Copy, Clone)]
+pub struct StopMode {
+    /// The voltage regulator mode.
+    pub regulator: StopRegulator,
+    /// Power down the flash memory, trading a slower wake-up for lower consumption.
+    pub flash_power_down: bool,
+    /// Let the regulator run in under-drive mode, which also powers down the flash memory.
+    #[cfg(any(
+        stm32_mcu = "stm32f427",
+        stm32_mcu = "stm32f429",
+        stm32_mcu = "stm32f437",
+        stm32_mcu = "stm32f439",
+        stm32_mcu = "stm32f446",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    pub under_drive: bool,
+}
+
+impl Pwr {
+    /// Enter Sleep mode and wait until an interrupt occurs.
+    pub fn sleep(&self, scb_scr: &scb::Scr<Srt>) {
+        scb_scr.modify(|r| r.clear_sleepdeep());
+        processor::wait_for_int();
+    }
+
+    /// Enter Stop mode while waiting for `wakeup` to complete, and return its output.
+    /// `wakeup` is typically the future of the EXTI line that wakes up the mcu.
+    ///
+    /// Stop mode is entered whenever the processor waits for an interrupt, i.e. when the program is otherwise idle,
+    /// until `wakeup` completes or the returned future is dropped.
+    /// Note that sysclk is sourced by the HSI after wake-up and that the HSE and the plls are disabled,
+    /// see `Rcc::stop()` for restoring the clock tree.
+    pub async fn stop<T>(
+        &self,
+        scb_scr: &scb::Scr<Srt>,
+        mode: StopMode,
+        wakeup: impl Future<Output = T>,
+    ) -> T {
+        self.pwr.pwr_cr.modify(|r| {
+            r.clear_pdds();
+            if mode.regulator == StopRegulator::LowPower {
+                r.set_lpds();
+            } else {
+                r.clear_lpds();
+            }
+            if mode.flash_power_down {
+                r.set_fpds();
+            } else {
+                r.clear_fpds();
+            }
+            #[cfg(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f446",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            ))]
+            if mode.under_drive {
+                r.write_uden(0b11).set_fpds();
+                if mode.regulator == StopRegulator::LowPower {
+                    r.set_lpuds().clear_mruds();
+                } else {
+                    r.set_mruds().clear_lpuds();
+                }
+            } else {
+                r.write_uden(0b00).clear_lpuds().clear_mruds();
+            }
+            r.set_cwuf()
+        });
+        let output = {
+            let _deep_sleep = DeepSleep::enter(scb_scr);
+            wakeup.await
+        };
+
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f446",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        if mode.under_drive {
+            // Clear the under-drive ready flag.
+            self.pwr.pwr_csr.modify(|r| r.write_udrdy(0b11));
+        }
+
+        output
     }
+
+    /// Enter Standby mode.
+    /// The mcu is reset when woken up by the WKUP pin, an RTC event, the IWDG, or the NRST pin.
+    pub fn standby(&self, scb_scr: &scb::Scr<Srt>) -> ! {
+        self.pwr.pwr_cr.modify(|r| r.set_pdds().set_cwuf());
+        scb_scr.modify(|r| r.set_sleepdeep());
+        loop {
+            processor::wait_for_int();
+        }
+    }
+
+    /// Set the main regulator output voltage scale.
+    /// The scale can only be changed while the pll is disabled and takes effect when the pll is enabled.
+    pub fn set_voltage_scale(&self, vos: VoltageScale) {
+        #[cfg(any(
+            stm32_mcu = "stm32f405",
+            stm32_mcu = "stm32f407",
+            stm32_mcu = "stm32f415",
+            stm32_mcu = "stm32f417",
+        ))]
+        self.pwr.pwr_cr.modify(|r| match vos {
+            VoltageScale::Scale1 => r.set_vos(),
+            VoltageScale::Scale2 => r.clear_vos(),
+        });
+        #[cfg(not(any(
+            stm32_mcu = "stm32f405",
+            stm32_mcu = "stm32f407",
+            stm32_mcu = "stm32f415",
+            stm32_mcu = "stm32f417",
+        )))]
+        self.pwr.pwr_cr.modify(|r| {
+            r.write_vos(match vos {
+                #[cfg(not(stm32_mcu = "stm32f401"))]
+                VoltageScale::Scale1 => 0b11,
+                VoltageScale::Scale2 => 0b10,
+                VoltageScale::Scale3 => 0b01,
+            })
+        });
+    }
+
+    /// Wait for the voltage scale to be ready after the pll is enabled.
+    pub fn wait_voltage_scale_ready(&self) {
+        #[cfg(not(any(
+            stm32_mcu = "stm32f405",
+            stm32_mcu = "stm32f407",
+            stm32_mcu = "stm32f415",
+            stm32_mcu = "stm32f417",
+        )))]
+        while !self.pwr.pwr_csr.vosrdy.read_bit() {}
+    }
+
+    /// Get whether the mcu was woken up from Standby mode and clear the flag.
+    pub fn take_standby_flag(&self) -> bool {
+        let standby = self.pwr.pwr_csr.sbf.read_bit();
+        self.pwr.pwr_cr.modify(|r| r.set_csbf());
+        standby
+    }
+
+    /// Let a rising edge on a WKUP pin wake up the mcu from Standby mode.
+    pub fn enable_wakeup_pin(&self, pin: WakeupPin) {
+        self.pwr.pwr_csr.modify(|r| match pin {
+            #[cfg(not(any(
+                stm32_mcu = "stm32f410",
+                stm32_mcu = "stm32f412",
+                stm32_mcu = "stm32f413",
+                stm32_mcu = "stm32f423",
+                stm32_mcu = "stm32f446",
+            )))]
+            WakeupPin::Wkup1 => r.set_ewup(),
+            #[cfg(any(
+                stm32_mcu = "stm32f410",
+                stm32_mcu = "stm32f412",
+                stm32_mcu = "stm32f413",
+                stm32_mcu = "stm32f423",
+                stm32_mcu = "stm32f446",
+            ))]
+            WakeupPin::Wkup1 => r.set_ewup1(),
+            #[cfg(any(
+                stm32_mcu = "stm32f410",
+                stm32_mcu = "stm32f412",
+                stm32_mcu = "stm32f413",
+                stm32_mcu = "stm32f423",
+                stm32_mcu = "stm32f446",
+            ))]
+            WakeupPin::Wkup2 => r.set_ewup2(),
+            #[cfg(any(
+                stm32_mcu = "stm32f410",
+                stm32_mcu = "stm32f412",
+                stm32_mcu = "stm32f413",
+                stm32_mcu = "stm32f423",
+            ))]
+            WakeupPin::Wkup3 => r.set_ewup3(),
+        });
+    }
+
+    /// Release a WKUP pin to general purpose use.
+    pub fn disable_wakeup_pin(&self, pin: WakeupPin) {
+        self.pwr.pwr_csr.modify(|r| match pin {
+            #[cfg(not(any(
+                stm32_mcu = "stm32f410",
+                stm32_mcu = "stm32f412",
+                stm32_mcu = "stm32f413",
+                stm32_mcu = "stm32f423",
+                stm32_mcu = "stm32f446",
+            )))]
+            WakeupPin::Wkup1 => r.clear_ewup(),
+            #[cfg(any(
+                stm32_mcu = "stm32f410",
+                stm32_mcu = "stm32f412",
+                stm32_mcu = "stm32f413",
+                stm32_mcu = "stm32f423",
+                stm32_mcu = "stm32f446",
+            ))]
+            WakeupPin::Wkup1 => r.clear_ewup1(),
+            #[cfg(any(
+                stm32_mcu = "stm32f410",
+                stm32_mcu = "stm32f412",
+                stm32_mcu = "stm32f413",
+                stm32_mcu = "stm32f423",
+                stm32_mcu = "stm32f446",
+            ))]
+            WakeupPin::Wkup2 => r.clear_ewup2(),
+            #[cfg(any(
+                stm32_mcu = "stm32f410",
+                stm32_mcu = "stm32f412",
+                stm32_mcu = "stm32f413",
+                stm32_mcu = "stm32f423",
+            ))]
+            WakeupPin::Wkup3 => r.clear_ewup3(),
+        });
+    }
+}
+
+/// A pin that can wake up the mcu from Standby mode on a rising edge.
+#[derive(Copy, Clone, PartialEq)]
+pub enum WakeupPin {
+    /// WKUP1 (PA0).
+    Wkup1,
+    /// WKUP2 (PC13 on the stm32f446, PC0 otherwise).
+    #[cfg(any(
+        stm32_mcu = "stm32f410",
+        stm32_mcu = "stm32f412",
+        stm32_mcu = "stm32f413",
+        stm32_mcu = "stm32f423",
+        stm32_mcu = "stm32f446",
+    ))]
+    Wkup2,
+    /// WKUP3 (PC1).
+    #[cfg(any(
+        stm32_mcu = "stm32f410",
+        stm32_mcu = "stm32f412",
+        stm32_mcu = "stm32f413",
+        stm32_mcu = "stm32f423",
+    ))]
+    Wkup3,
+}
+
+/// Deep sleep is enabled while the guard is alive,
+/// such that the processor enters Stop mode instead of Sleep mode when it waits for an interrupt.
+struct DeepSleep<'a> {
+    scb_scr: &'a scb::Scr<Srt>,
 }
 
-// STM32F42xxx and STM32F43xxx
+impl<'a> DeepSleep<'a> {
+    fn enter(scb_scr: &'a scb::Scr<Srt>) -> Self {
+        scb_scr.modify(|r| r.set_sleepdeep());
+        Self { scb_scr }
+    }
+}
+
+impl Drop for DeepSleep<'_> {
+    fn drop(&mut self) {
+        self.scb_scr.modify(|r| r.clear_sleepdeep());
+    }
+}
+
+/// A token proving that write access to the backup domain is enabled.
+#[derive(Copy, Clone)]
+pub struct BackupDomainAccess {
+    _private: (),
+}
+
+// STM32F42xxx, STM32F43xxx, STM32F446xx, and STM32F469xx/79xx
 #[cfg(any(
     stm32_mcu = "stm32f427",
     stm32_mcu = "stm32f429",
     stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
 ))]
 impl Overdriveable for Pwr {
     fn enable_overdrive(&self) {
@@ -39,3 +349,99 @@ impl Overdriveable for Pwr {
         }
     }
 }
+
+// STM32F42xxx, STM32F43xxx, STM32F446xx, and STM32F469xx/79xx
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl Pwr {
+    /// Enable over-drive while yielding to the executor between the polls of the ready flags.
+    ///
+    /// The pll must be enabled and the voltage scale must be set to scale 1 before over-drive is enabled.
+    pub async fn enable_overdrive_async(&self) {
+        // Enable the Over-drive mode and wait for the ODRDY flag to be set.
+        self.pwr.pwr_cr.modify(|r| r.set_oden());
+        BusyPollFlag(|| self.pwr.pwr_csr.odrdy.read_bit()).await;
+
+        // Set the ODSW bit in the PWR_CR register to switch the voltage regulator from Normal mode to Over-drive mode.
+        self.pwr.pwr_cr.modify(|r| r.set_odswen());
+        BusyPollFlag(|| self.pwr.pwr_csr.odswrdy.read_bit()).await;
+    }
+
+    /// Disable over-drive.
+    ///
+    /// The sysclk must be decreased to the maximum frequency without over-drive
+    /// and be sourced by the HSI or the HSE before over-drive is disabled.
+    pub async fn disable_overdrive(&self) {
+        // Switch the voltage regulator back to Normal mode and wait for the ODSWRDY flag to be cleared.
+        self.pwr.pwr_cr.modify(|r| r.clear_odswen());
+        BusyPollFlag(|| !self.pwr.pwr_csr.odswrdy.read_bit()).await;
+
+        // Disable the Over-drive mode.
+        self.pwr.pwr_cr.modify(|r| r.clear_oden());
+    }
+
+    /// Get whether the voltage regulator has switched to Over-drive mode.
+    pub fn is_overdrive(&self) -> bool {
+        self.pwr.pwr_csr.odswrdy.read_bit()
+    }
+}
+
+#[cfg(not(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+)))]
+impl Pwr {
+    /// Get whether the voltage regulator has switched to Over-drive mode,
+    /// which is never the case as over-drive is not supported.
+    pub fn is_overdrive(&self) -> bool {
+        false
+    }
+}
+
+/// A future that busy-polls a pwr status flag until it is set.
+/// The pwr flags have no interrupt, so the future wakes itself on every poll,
+/// i.e. the executor keeps polling it and the thread never sleeps while it is pending.
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+struct BusyPollFlag<F: FnMut() -> bool + Unpin>(F);
+
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl<F: FnMut() -> bool + Unpin> Future for BusyPollFlag<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if (self.0)() {
+            Poll::Ready(())
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
@@ -1,8 +1,17 @@
-use crate::{traits::*, clktree::*, diverged::RccDiverged, periph::RccPeriph};
+use crate::{
+    traits::*,
+    clktree::*,
+    diverged::RccDiverged,
+    flash::Flash,
+    periph::RccPeriph,
+    pwr::{BackupDomainAccess, Pwr, StopMode},
+};
+use alloc::{boxed::Box, vec::Vec};
 use core::cell::RefCell;
+use core::future::Future;
 use core::marker::PhantomData;
 use drone_core::bitfield::Bitfield;
-use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
+use drone_cortexm::{fib, map::reg::scb, reg::prelude::*, thr::prelude::*};
 use drone_stm32_map::reg;
 use fib::FiberFuture;
 
@@ -21,14 +30,16 @@ impl<RccInt: IntToken> RccSetup<RccInt> {
 }
 
 #[derive(Clone, Copy, Bitfield)]
-#[bitfield(hclk(rw, 0), pclk1(rw, 1), pclk2(rw, 2))]
+#[bitfield(hclk(rw, 0), pclk1(rw, 1), pclk2(rw, 2), timclk(rw, 3), overdrive(rw, 4))]
 struct ConfiguredClocks(u8);
 
 /// Rcc controller.
 pub struct Rcc<RccInt: IntToken> {
-    rcc: RccDiverged,
+    pub(crate) rcc: RccDiverged,
     rcc_int: RccInt,
     configured: RefCell<ConfiguredClocks>,
+    applied: RefCell<Option<ClockTree>>,
+    listeners: RefCell<Vec<Box<dyn FnMut(&ConfiguredClockTree)>>>,
 }
 
 impl<RccInt: IntToken> Rcc<RccInt> {
@@ -39,6 +50,8 @@ impl<RccInt: IntToken> Rcc<RccInt> {
             rcc: rcc.into(),
             rcc_int,
             configured: RefCell::new(ConfiguredClocks(0)),
+            applied: RefCell::new(None),
+            listeners: RefCell::new(Vec::new()),
         }
     }
 }
@@ -72,11 +85,276 @@ impl<RccInt: IntToken> StabilizingClkCtrl<HseClk> for Rcc<RccInt> {
     }
 }
 
+/// The clock security system (CSS) policy when the HSE fails.
+#[derive(Copy, Clone, PartialEq)]
+pub enum CssPolicy {
+    /// Only report the failure.
+    /// The hardware itself switches sysclk to HSI and disables the HSE, and the pll if it sources sysclk from the HSE,
+    /// so in practice this only differs from `FallbackToHsi` in that a pll that runs from the HSE
+    /// without sourcing sysclk is left enabled, although it has lost its lock.
+    Report,
+    /// Switch sysclk to HSI, disable the pll if it runs from the HSE, and report the failure.
+    FallbackToHsi,
+}
+
+/// A HSE failure detected by the clock security system.
+#[derive(Copy, Clone)]
+pub struct HseFailure {
+    /// The clocks that are in effect after the failure,
+    /// or `None` if the clock tree last applied by `apply()` does not use the HSE.
+    pub fallback: Option<ConfiguredClockTree>,
+}
+
+impl<RccInt: IntToken> Rcc<RccInt> {
+    /// Enable the clock security system (CSS) that monitors the HSE.
+    /// The CSS interrupt is routed to the non-maskable interrupt (NMI), so `nmi` must be the NMI thread token.
+    /// The returned future completes when a HSE failure is detected.
+    /// The NMI handler only acknowledges the failure, and any fallback required by `policy`
+    /// is done by the returned future in the thread that awaits it,
+    /// as the NMI cannot be masked during the read-modify-writes of the clock registers.
+    /// If the applied clock tree uses the HSE, it is replaced by a tree where sysclk is sourced by the HSI,
+    /// and the listeners registered by `on_apply()` are notified, e.g. to let the drivers reprogram their baud rates.
+    pub fn enable_css<Nmi: ThrToken>(
+        &self,
+        nmi: Nmi,
+        _hse: ConfiguredClk<HseClk>,
+        policy: CssPolicy,
+    ) -> impl Future<Output = HseFailure> + '_ {
+        let reg::rcc::Cir { cssc, cssf, .. } = self.rcc.rcc_cir;
+
+        // Attach a listener that will notify us when the HSE fails.
+        let failure = nmi.add_future(fib::new_fn(move || {
+            if cssf.read_bit() {
+                // The flag must be cleared, otherwise the NMI is re-entered.
+                cssc.set_bit();
+                fib::Complete(())
+            } else {
+                fib::Yielded(())
+            }
+        }));
+
+        // Enable the clock security system.
+        self.rcc.rcc_cr.modify(|r| r.set_csson());
+
+        async move {
+            failure.await;
+
+            if policy == CssPolicy::FallbackToHsi {
+                self.fallback_to_hsi();
+            }
+
+            let applied = *self.applied.borrow();
+            let fallback = match applied {
+                Some(tree) if tree.hse().is_some() => {
+                    let tree = tree.to_hsi_fallback();
+                    let clocks = ConfiguredClockTree::from_tree(&tree);
+                    self.applied.replace(Some(tree));
+                    self.notify(&clocks);
+                    Some(clocks)
+                }
+                _ => None,
+            };
+            HseFailure { fallback }
+        }
+    }
+
+    /// Disable the clock security system.
+    pub fn disable_css(&self) {
+        self.rcc.rcc_cr.modify(|r| r.clear_csson());
+    }
+
+    /// Switch sysclk to HSI and disable the pll if they depend on the failed HSE.
+    fn fallback_to_hsi(&self) {
+        let pll_hse = self.rcc.rcc_pllcfgr.load().pllsrc();
+        let sws = self.rcc.rcc_cfgr.load().sws();
+        if sws == 0b01 || (sws == 0b10 && pll_hse) {
+            self.rcc.rcc_cfgr.modify(|r| r.write_sw(0b00));
+            while self.rcc.rcc_cfgr.load().sws() != 0b00 {}
+        }
+        // A pll that runs from the HSI is still usable.
+        if pll_hse {
+            self.rcc.rcc_cr.modify(|r| r.clear_pllon());
+        }
+    }
+}
+
+pub(crate) fn hpre_div(hpre: u32) -> u32 {
+    match hpre {
+        0b1000 => 2,
+        0b1001 => 4,
+        0b1010 => 8,
+        0b1011 => 16,
+        0b1100 => 64,
+        0b1101 => 128,
+        0b1110 => 256,
+        0b1111 => 512,
+        _ => 1,
+    }
+}
+
+pub(crate) fn ppre_div(ppre: u32) -> u32 {
+    match ppre {
+        0b100 => 2,
+        0b101 => 4,
+        0b110 => 8,
+        0b111 => 16,
+        _ => 1,
+    }
+}
+
 pub struct ConfiguredClkBuilder<'a, RccInt: IntToken, Clk> {
     pub(crate) rcc: &'a Rcc<RccInt>,
     pub(crate) clk: PhantomData<Clk>,
 }
 
+impl<RccInt: IntToken> Rcc<RccInt> {
+    /// Get a builder for the clocks that reside in the backup domain, i.e. the LSE and the RTC clock.
+    pub fn with_backup_domain_access(
+        &self,
+        _access: BackupDomainAccess,
+    ) -> ConfiguredClkBuilder<'_, RccInt, BackupDomainAccess> {
+        ConfiguredClkBuilder {
+            rcc: self,
+            clk: PhantomData,
+        }
+    }
+
+    fn select_rtcclk(&self, signal: RtcClkMuxSignal, rtcsel: u32) -> ConfiguredClk<RtcClk> {
+        // The rtc clock source can only be selected once until the backup domain is reset.
+        let current = self.rcc.rcc_bdcr.load().rtcsel();
+        if current != 0b00 && current != rtcsel {
+            panic!("Reset the backup domain before selecting a different RTC clock source.");
+        }
+
+        // Select the clock and enable the rtc clock.
+        self.rcc.rcc_bdcr.modify(|r| r.write_rtcsel(rtcsel).set_rtcen());
+        ConfiguredClk {
+            clk: signal.to_rtcclk(),
+        }
+    }
+}
+
+impl<RccInt: IntToken> StabilizingClkCtrl<LsiClk> for Rcc<RccInt> {
+    fn stabilize(&self, clk: LsiClk) -> FiberFuture<ConfiguredClk<LsiClk>> {
+        assert!(self.rcc_int.is_int_enabled());
+
+        // Enable ready interrupt.
+        self.rcc.rcc_cir.modify(|r| r.set_lsirdyie());
+
+        let reg::rcc::Cir {
+            lsirdyc, lsirdyf, ..
+        } = self.rcc.rcc_cir;
+
+        // Attach a listener that will notify us when the clock has stabilized.
+        let lsirdy = self.rcc_int.add_future(fib::new_fn(move || {
+            if lsirdyf.read_bit() {
+                lsirdyc.set_bit();
+                fib::Complete(ConfiguredClk { clk })
+            } else {
+                fib::Yielded(())
+            }
+        }));
+
+        // Enable the clock.
+        self.rcc.rcc_csr.modify(|r| r.set_lsion());
+
+        // Wait for the clock to stabilize.
+        lsirdy
+    }
+}
+
+impl<RccInt: IntToken> StabilizingClkCtrl<LseClk>
+    for ConfiguredClkBuilder<'_, RccInt, BackupDomainAccess>
+{
+    fn stabilize(&self, clk: LseClk) -> FiberFuture<ConfiguredClk<LseClk>> {
+        let rcc = self.rcc;
+        assert!(rcc.rcc_int.is_int_enabled());
+
+        // Enable ready interrupt.
+        rcc.rcc.rcc_cir.modify(|r| r.set_lserdyie());
+
+        let reg::rcc::Cir {
+            lserdyc, lserdyf, ..
+        } = rcc.rcc.rcc_cir;
+
+        // Attach a listener that will notify us when the clock has stabilized.
+        let lserdy = rcc.rcc_int.add_future(fib::new_fn(move || {
+            if lserdyf.read_bit() {
+                lserdyc.set_bit();
+                fib::Complete(ConfiguredClk { clk })
+            } else {
+                fib::Yielded(())
+            }
+        }));
+
+        // Configure the clock while the oscillator is disabled.
+        rcc.rcc.rcc_bdcr.modify(|r| r.clear_lseon());
+        rcc.rcc.rcc_bdcr.modify(|r| {
+            #[cfg(any(
+                stm32_mcu = "stm32f410",
+                stm32_mcu = "stm32f411",
+                stm32_mcu = "stm32f412",
+                stm32_mcu = "stm32f413",
+                stm32_mcu = "stm32f423",
+                stm32_mcu = "stm32f446",
+            ))]
+            match clk.drive {
+                LseDrive::LowPower => r.clear_lsemod(),
+                LseDrive::HighDrive => r.set_lsemod(),
+            };
+            if clk.bypass {
+                r.set_lsebyp()
+            } else {
+                r.clear_lsebyp()
+            }
+        });
+
+        // Enable the clock.
+        rcc.rcc.rcc_bdcr.modify(|r| r.set_lseon());
+
+        // Wait for the clock to stabilize.
+        lserdy
+    }
+}
+
+impl<'a, RccInt: IntToken> MuxCtrl<'a, RccInt, RtcClkMuxSignal, LseClk>
+    for ConfiguredClkBuilder<'_, RccInt, BackupDomainAccess>
+{
+    type Builder = ConfiguredClk<RtcClk>;
+
+    fn select(&'a self, signal: RtcClkMuxSignal, _clk: ConfiguredClk<LseClk>) -> Self::Builder {
+        assert!(matches!(signal, RtcClkMuxSignal::Lse { .. }));
+        self.rcc.select_rtcclk(signal, 0b01)
+    }
+}
+
+impl<'a, RccInt: IntToken> MuxCtrl<'a, RccInt, RtcClkMuxSignal, LsiClk>
+    for ConfiguredClkBuilder<'_, RccInt, BackupDomainAccess>
+{
+    type Builder = ConfiguredClk<RtcClk>;
+
+    fn select(&'a self, signal: RtcClkMuxSignal, _clk: ConfiguredClk<LsiClk>) -> Self::Builder {
+        assert!(matches!(signal, RtcClkMuxSignal::Lsi { .. }));
+        self.rcc.select_rtcclk(signal, 0b10)
+    }
+}
+
+impl<'a, RccInt: IntToken> MuxCtrl<'a, RccInt, RtcClkMuxSignal, HseClk>
+    for ConfiguredClkBuilder<'_, RccInt, BackupDomainAccess>
+{
+    type Builder = ConfiguredClk<RtcClk>;
+
+    fn select(&'a self, signal: RtcClkMuxSignal, _clk: ConfiguredClk<HseClk>) -> Self::Builder {
+        if let RtcClkMuxSignal::Hse(clk) = signal {
+            // Set the hse division factor before the clock is selected.
+            self.rcc.rcc.rcc_cfgr.modify(|r| r.write_rtcpre(clk.rtcpre));
+        } else {
+            panic!("Invalid RTC clock source signal.");
+        }
+        self.rcc.select_rtcclk(signal, 0b11)
+    }
+}
+
 impl<RccInt: IntToken, SrcClk> StabilizingClkCtrl<Pll>
     for ConfiguredClkBuilder<'_, RccInt, SrcClk>
 {
@@ -102,16 +380,27 @@ impl<RccInt: IntToken, SrcClk> StabilizingClkCtrl<Pll>
         }));
 
         // Configure the clock.
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        rcc.set_shared_pllm(clk.vco.src.m);
         rcc.rcc.rcc_pllcfgr.modify(|r| {
-            let pllp = match clk.p.div {
-                2 => 0b00,
-                4 => 0b01,
-                6 => 0b10,
-                8 => 0b11,
-                _ => unreachable!(),
-            };
-            r.write_pllm(clk.vco.src.m)
-                .write_plln(clk.vco.n)
+            let pllp = pllp_bits(clk.p.div);
+            #[cfg(not(any(
+                stm32_mcu = "stm32f427",
+                stm32_mcu = "stm32f429",
+                stm32_mcu = "stm32f437",
+                stm32_mcu = "stm32f439",
+                stm32_mcu = "stm32f469",
+                stm32_mcu = "stm32f479",
+            )))]
+            r.write_pllm(clk.vco.src.m);
+            r.write_plln(clk.vco.n)
                 .write_pllp(pllp)
                 .write_pllq(clk.q.div)
         });
@@ -124,6 +413,179 @@ impl<RccInt: IntToken, SrcClk> StabilizingClkCtrl<Pll>
     }
 }
 
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl<RccInt: IntToken, SrcClk> StabilizingClkCtrl<PllI2s>
+    for ConfiguredClkBuilder<'_, RccInt, SrcClk>
+{
+    fn stabilize(&self, clk: PllI2s) -> FiberFuture<ConfiguredClk<PllI2s>> {
+        let rcc = self.rcc;
+        assert!(rcc.rcc_int.is_int_enabled());
+
+        // Enable ready interrupt.
+        rcc.rcc.rcc_cir.modify(|r| r.set_plli2srdyie());
+
+        let reg::rcc::Cir {
+            plli2srdyc,
+            plli2srdyf,
+            ..
+        } = rcc.rcc.rcc_cir;
+
+        // Attach a listener that will notify us when the clock has stabilized.
+        let plli2srdy = rcc.rcc_int.add_future(fib::new_fn(move || {
+            if plli2srdyf.read_bit() {
+                plli2srdyc.set_bit();
+                fib::Complete(ConfiguredClk { clk })
+            } else {
+                fib::Yielded(())
+            }
+        }));
+
+        // Configure the clock.
+        #[cfg(not(stm32_mcu = "stm32f446"))]
+        rcc.set_shared_pllm(clk.vco.src.m);
+        rcc.rcc.rcc_plli2scfgr.modify(|r| {
+            #[cfg(stm32_mcu = "stm32f446")]
+            r.write_plli2sm(clk.vco.src.m);
+            r.write_plli2sn(clk.vco.n)
+                .write_plli2sq(clk.q.div)
+                .write_plli2sr(clk.r.div)
+        });
+
+        // Enable the clock.
+        rcc.rcc.rcc_cr.modify(|r| r.set_plli2son());
+
+        // Wait for the clock to stabilize.
+        plli2srdy
+    }
+}
+
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl<RccInt: IntToken, SrcClk> StabilizingClkCtrl<PllSai>
+    for ConfiguredClkBuilder<'_, RccInt, SrcClk>
+{
+    fn stabilize(&self, clk: PllSai) -> FiberFuture<ConfiguredClk<PllSai>> {
+        let rcc = self.rcc;
+        assert!(rcc.rcc_int.is_int_enabled());
+
+        // Enable ready interrupt.
+        rcc.rcc.rcc_cir.modify(|r| r.set_pllsairdyie());
+
+        let reg::rcc::Cir {
+            pllsairdyc,
+            pllsairdyf,
+            ..
+        } = rcc.rcc.rcc_cir;
+
+        // Attach a listener that will notify us when the clock has stabilized.
+        let pllsairdy = rcc.rcc_int.add_future(fib::new_fn(move || {
+            if pllsairdyf.read_bit() {
+                pllsairdyc.set_bit();
+                fib::Complete(ConfiguredClk { clk })
+            } else {
+                fib::Yielded(())
+            }
+        }));
+
+        // Configure the clock.
+        #[cfg(not(stm32_mcu = "stm32f446"))]
+        rcc.set_shared_pllm(clk.vco.src.m);
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+        ))]
+        rcc.rcc.rcc_pllsaicfgr.modify(|r| {
+            r.write_pllsain(clk.vco.n)
+                .write_pllsaiq(clk.q.div)
+                .write_pllsair(clk.r.div)
+        });
+        #[cfg(any(stm32_mcu = "stm32f469", stm32_mcu = "stm32f479"))]
+        rcc.rcc.rcc_pllsaicfgr.modify(|r| {
+            r.write_pllsain(clk.vco.n)
+                .write_pllsaip(pllp_bits(clk.p.div))
+                .write_pllsaiq(clk.q.div)
+                .write_pllsair(clk.r.div)
+        });
+        #[cfg(not(stm32_mcu = "stm32f446"))]
+        rcc.rcc.rcc_dckcfgr.modify(|r| {
+            let pllsaidivr = match clk.ltdc.divr {
+                2 => 0b00,
+                4 => 0b01,
+                8 => 0b10,
+                16 => 0b11,
+                _ => unreachable!(),
+            };
+            r.write_pllsaidivr(pllsaidivr)
+        });
+        #[cfg(stm32_mcu = "stm32f446")]
+        rcc.rcc.rcc_pllsaicfgr.modify(|r| {
+            r.write_pllsaim(clk.vco.src.m)
+                .write_pllsain(clk.vco.n)
+                .write_pllsaip(pllp_bits(clk.p.div))
+                .write_pllsaiq(clk.q.div)
+        });
+
+        // Enable the clock.
+        rcc.rcc.rcc_cr.modify(|r| r.set_pllsaion());
+
+        // Wait for the clock to stabilize.
+        pllsairdy
+    }
+}
+
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl<RccInt: IntToken> Rcc<RccInt> {
+    /// Set the pll input division factor that is shared among the main pll, the plli2s, and the pllsai.
+    fn set_shared_pllm(&self, m: u32) {
+        let cr = self.rcc.rcc_cr.load();
+        if cr.pllon() || cr.plli2son() || cr.pllsaion() {
+            // The divider is in use and cannot be changed.
+            assert_eq!(
+                m,
+                self.rcc.rcc_pllcfgr.load().pllm(),
+                "The pll input division factor m must be the same for all plls."
+            );
+        } else {
+            self.rcc.rcc_pllcfgr.modify(|r| r.write_pllm(m));
+        }
+    }
+}
+
+/// Get the register value of a pll p division factor.
+fn pllp_bits(div: u32) -> u32 {
+    match div {
+        2 => 0b00,
+        4 => 0b01,
+        6 => 0b10,
+        8 => 0b11,
+        _ => unreachable!(),
+    }
+}
+
 impl ConfiguredClk<Pll> {
     pub fn p(self) -> ConfiguredClk<PllClk<PllP>> {
         ConfiguredClk { clk: self.clk.p }
@@ -134,6 +596,59 @@ impl ConfiguredClk<Pll> {
     }
 }
 
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl ConfiguredClk<PllI2s> {
+    pub fn q(self) -> ConfiguredClk<PllClk<PllI2sQ>> {
+        ConfiguredClk { clk: self.clk.q }
+    }
+
+    pub fn r(self) -> ConfiguredClk<PllClk<PllI2sR>> {
+        ConfiguredClk { clk: self.clk.r }
+    }
+}
+
+#[cfg(any(
+    stm32_mcu = "stm32f427",
+    stm32_mcu = "stm32f429",
+    stm32_mcu = "stm32f437",
+    stm32_mcu = "stm32f439",
+    stm32_mcu = "stm32f446",
+    stm32_mcu = "stm32f469",
+    stm32_mcu = "stm32f479",
+))]
+impl ConfiguredClk<PllSai> {
+    #[cfg(any(
+        stm32_mcu = "stm32f446",
+        stm32_mcu = "stm32f469",
+        stm32_mcu = "stm32f479",
+    ))]
+    pub fn p(self) -> ConfiguredClk<PllClk<PllSaiP>> {
+        ConfiguredClk { clk: self.clk.p }
+    }
+
+    pub fn q(self) -> ConfiguredClk<PllClk<PllSaiQ>> {
+        ConfiguredClk { clk: self.clk.q }
+    }
+
+    #[cfg(not(stm32_mcu = "stm32f446"))]
+    pub fn r(self) -> ConfiguredClk<PllClk<PllSaiR>> {
+        ConfiguredClk { clk: self.clk.r }
+    }
+
+    #[cfg(not(stm32_mcu = "stm32f446"))]
+    pub fn ltdc(self) -> ConfiguredClk<LtdcClk> {
+        ConfiguredClk { clk: self.clk.ltdc }
+    }
+}
+
 impl<RccInt: IntToken> ClkCtrl<HClk> for Rcc<RccInt> {
     fn configure(&self, clk: HClk) -> ConfiguredClk<HClk> {
         self.configured.borrow_mut().set_hclk();
@@ -192,6 +707,57 @@ impl<RccInt: IntToken> ClkCtrl<PClk2> for Rcc<RccInt> {
     }
 }
 
+impl<RccInt: IntToken> ClkCtrl<TimClk1> for Rcc<RccInt> {
+    fn configure(&self, clk: TimClk1) -> ConfiguredClk<TimClk1> {
+        self.configure_timpre(clk.timpre);
+        ConfiguredClk { clk }
+    }
+}
+
+impl<RccInt: IntToken> ClkCtrl<TimClk2> for Rcc<RccInt> {
+    fn configure(&self, clk: TimClk2) -> ConfiguredClk<TimClk2> {
+        self.configure_timpre(clk.timpre);
+        ConfiguredClk { clk }
+    }
+}
+
+impl<RccInt: IntToken> Rcc<RccInt> {
+    /// Configure the timers clock prescaler selection which is shared among the APB1 and APB2 timers.
+    fn configure_timpre(&self, timpre: TimPre) {
+        #[cfg(not(any(
+            stm32_mcu = "stm32f405",
+            stm32_mcu = "stm32f407",
+            stm32_mcu = "stm32f415",
+            stm32_mcu = "stm32f417",
+        )))]
+        {
+            let mul4 = matches!(timpre, TimPre::Mul4);
+            if self.configured.borrow().timclk() {
+                assert_eq!(
+                    mul4,
+                    self.rcc.rcc_dckcfgr.load().timpre(),
+                    "TIMCLK1 and TIMCLK2 must use the same TIMPRE setting."
+                );
+            }
+            self.rcc.rcc_dckcfgr.modify(|r| {
+                if mul4 {
+                    r.set_timpre()
+                } else {
+                    r.clear_timpre()
+                }
+            });
+        }
+        #[cfg(any(
+            stm32_mcu = "stm32f405",
+            stm32_mcu = "stm32f407",
+            stm32_mcu = "stm32f415",
+            stm32_mcu = "stm32f417",
+        ))]
+        let _ = timpre;
+        self.configured.borrow_mut().set_timclk();
+    }
+}
+
 impl<'a, RccInt: IntToken> MuxCtrl<'a, RccInt, PllSrcMuxSignal, HsiClk> for Rcc<RccInt> {
     type Builder = ConfiguredClkBuilder<'a, RccInt, HsiClk>;
 
@@ -272,10 +838,13 @@ impl<'a, RccInt: IntToken> MuxCtrl<'a, RccInt, SysClkMuxSignal, PllClk<PllP>> fo
         // We need to make sure that HCLK, PCLK1, and PCLK2 are configured
         // to avoid overclocking of their max bus frequencies when setting the PLL as source.
         // Other sysclk signals are not fast enough to overclock the three buses.
-        if self.configured.borrow().0 != 0b111 {
+        let configured = *self.configured.borrow();
+        if !(configured.hclk() && configured.pclk1() && configured.pclk2()) {
             panic!("Configure HCLK, PCLK1, and PCLK2 before selecting PLL as source.");
         }
-        assert_eq!(0b111, self.configured.borrow().0);
+        if signal.f() > SYSCLK_MAX_NO_OVERDRIVE && !configured.overdrive() {
+            panic!("Confirm over-drive before selecting PLL as source above the maximum frequency without over-drive.");
+        }
 
         self.rcc.rcc_cfgr.modify(|r| r.write_sw(0b10));
         ConfiguredClkBuilder {
@@ -284,3 +853,307 @@ impl<'a, RccInt: IntToken> MuxCtrl<'a, RccInt, SysClkMuxSignal, PllClk<PllP>> fo
         }
     }
 }
+
+/// The configured clocks of an applied clock tree.
+#[derive(Copy, Clone)]
+pub struct ConfiguredClockTree {
+    pub hse: Option<ConfiguredClk<HseClk>>,
+    pub pll: Option<ConfiguredClk<Pll>>,
+    pub sysclk: ConfiguredClk<SysClk>,
+    pub hclk: ConfiguredClk<HClk>,
+    pub pclk1: ConfiguredClk<PClk1>,
+    pub pclk2: ConfiguredClk<PClk2>,
+    pub timclk1: ConfiguredClk<TimClk1>,
+    pub timclk2: ConfiguredClk<TimClk2>,
+    pub systickclk: ConfiguredClk<SysTickClk>,
+}
+
+impl ConfiguredClockTree {
+    /// Get the configured clocks of a tree that is already in effect and does not use the HSE.
+    fn from_tree(tree: &ClockTree) -> ConfiguredClockTree {
+        ConfiguredClockTree {
+            hse: None,
+            pll: tree.pll.map(|clk| ConfiguredClk { clk }),
+            sysclk: ConfiguredClk { clk: tree.sysclk() },
+            hclk: ConfiguredClk { clk: tree.hclk() },
+            pclk1: ConfiguredClk { clk: tree.pclk1 },
+            pclk2: ConfiguredClk { clk: tree.pclk2 },
+            timclk1: ConfiguredClk { clk: tree.timclk1() },
+            timclk2: ConfiguredClk { clk: tree.timclk2() },
+            systickclk: ConfiguredClk {
+                clk: tree.hclk().to_systickclk(),
+            },
+        }
+    }
+}
+
+impl<RccInt: IntToken> Rcc<RccInt> {
+    /// Get the HSI trimming value, i.e. HSITRIM.
+    pub fn hsi_trim(&self) -> u32 {
+        self.rcc.rcc_cr.load().hsitrim()
+    }
+
+    /// Set the HSI trimming value, i.e. HSITRIM, where the default value 16 gives the factory calibration.
+    pub fn set_hsi_trim(&self, trim: u32) {
+        assert!(trim <= HSI_TRIM_MAX);
+        self.rcc.rcc_cr.modify(|r| r.write_hsitrim(trim));
+    }
+
+    /// Confirm the current over-drive state of the voltage regulator.
+    /// Over-drive must be confirmed before the pll is selected as sysclk source above the maximum frequency without over-drive.
+    pub fn confirm_overdrive(&self, pwr: &Pwr) {
+        let mut configured = self.configured.borrow_mut();
+        if pwr.is_overdrive() {
+            configured.set_overdrive();
+        } else {
+            configured.clear_overdrive();
+        }
+    }
+
+    /// Register a listener that is called every time a clock tree has been applied by `apply()`,
+    /// e.g. to let a driver reprogram its clock dividers.
+    pub fn on_apply(&self, listener: impl FnMut(&ConfiguredClockTree) + 'static) {
+        self.listeners.borrow_mut().push(Box::new(listener));
+    }
+}
+
+impl<RccInt: IntToken> Rcc<RccInt> {
+    /// Apply a clock tree, either on boot or at runtime, e.g. to scale sysclk down and back up again.
+    ///
+    /// The steps are ordered such that no clock is overclocked during the transition:
+    /// The oscillators are stabilized, over-drive is enabled, and the flash latency is increased before sysclk is increased,
+    /// and the flash latency is decreased after sysclk is decreased.
+    /// A running pll is reconfigured and over-drive is disabled while sysclk is temporarily sourced by the HSI,
+    /// and the pll is disabled if it is no longer used.
+    /// The HSE is disabled if it is neither used by the tree nor by any other clock.
+    /// The listeners registered by `on_apply()` are notified when the tree is in effect.
+    pub async fn apply(
+        &self,
+        tree: ClockTree,
+        pwr: &Pwr,
+        flash: &Flash,
+    ) -> ConfiguredClockTree {
+        let hsi = ConfiguredClk { clk: HsiClk };
+        let applied = *self.applied.borrow();
+        let mut current_f = applied.map_or(HsiClk.f(), |applied| applied.sysclk().f());
+
+        // The pll cannot be reconfigured while it is running,
+        // and over-drive can only be disabled while sysclk is sourced by the HSI or the HSE.
+        let cr = self.rcc.rcc_cr.load();
+        let reuse_pll = cr.pllrdy()
+            && tree.pll.is_some()
+            && applied.map_or(false, |applied| applied.pll == tree.pll && applied.vos == tree.vos);
+        let disable_overdrive = !tree.sysclk().overdrive && pwr.is_overdrive();
+        if (cr.pllon() && !reuse_pll) || disable_overdrive {
+            if self.rcc.rcc_cfgr.load().sws() == 0b10 {
+                self.switch_sysclk(SysClkMuxSignal::Hsi(HsiClk).to_sysclk(), hsi, None, None);
+                current_f = HsiClk.f();
+            }
+        }
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f446",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        if disable_overdrive {
+            pwr.disable_overdrive().await;
+            self.confirm_overdrive(pwr);
+        }
+        if cr.pllon() && !reuse_pll {
+            self.disable_pll();
+        }
+
+        // The voltage scale can only be changed while the pll is disabled.
+        if !reuse_pll {
+            pwr.set_voltage_scale(tree.vos);
+        }
+
+        // Stabilize the oscillators.
+        let hse = match tree.hse() {
+            Some(clk) if cr.hserdy() => Some(ConfiguredClk { clk }),
+            Some(clk) => Some(self.stabilize(clk).await),
+            None => None,
+        };
+        let pll = match tree.pll {
+            Some(clk) if reuse_pll => Some(ConfiguredClk { clk }),
+            Some(clk) => Some(match clk.vco.src.mux {
+                signal @ PllSrcMuxSignal::Hsi(_) => self.select(signal, hsi).stabilize(clk).await,
+                signal @ PllSrcMuxSignal::Hse(_) => {
+                    self.select(signal, hse.unwrap()).stabilize(clk).await
+                }
+            }),
+            None => None,
+        };
+        if pll.is_some() && !reuse_pll {
+            pwr.wait_voltage_scale_ready();
+        }
+
+        // Over-drive must be enabled before sysclk is increased above 168MHz.
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f446",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        if tree.sysclk().overdrive && !pwr.is_overdrive() {
+            pwr.enable_overdrive_async().await;
+        }
+        self.confirm_overdrive(pwr);
+
+        // The flash latency must be increased before and decreased after the sysclk frequency is changed.
+        let wait_states = tree.hclk().get_wait_states(tree.voltage);
+        if wait_states > flash.latency() {
+            flash.set_latency(wait_states);
+        }
+
+        // The bus prescalers are configured before sysclk is increased and after it is decreased,
+        // such that the buses are not overclocked in between.
+        let sysclk = tree.sysclk();
+        let increase = sysclk.f() >= current_f;
+        if !increase {
+            self.switch_sysclk(sysclk, hsi, hse, pll);
+        }
+        let hclk = self.configure(tree.hclk());
+        let pclk1 = self.configure(tree.pclk1);
+        let pclk2 = self.configure(tree.pclk2);
+        let timclk1 = self.configure(tree.timclk1());
+        let timclk2 = self.configure(tree.timclk2());
+        if increase {
+            self.switch_sysclk(sysclk, hsi, hse, pll);
+        }
+
+        if wait_states < flash.latency() {
+            flash.set_latency(wait_states);
+        }
+
+        // Disable the pll if it is no longer used.
+        if tree.pll.is_none() && self.rcc.rcc_cr.load().pllon() {
+            self.disable_pll();
+        }
+
+        // Disable the HSE if it is no longer used.
+        if hse.is_none() && self.rcc.rcc_cr.load().hseon() && !self.is_hse_used() {
+            self.disable_hse();
+        }
+
+        let clocks = ConfiguredClockTree {
+            hse,
+            pll,
+            sysclk: ConfiguredClk { clk: sysclk },
+            hclk,
+            pclk1,
+            pclk2,
+            timclk1,
+            timclk2,
+            systickclk: ConfiguredClk {
+                clk: tree.hclk().to_systickclk(),
+            },
+        };
+
+        self.applied.replace(Some(tree));
+        self.notify(&clocks);
+
+        clocks
+    }
+
+    /// Notify the listeners registered by `on_apply()`.
+    fn notify(&self, clocks: &ConfiguredClockTree) {
+        // The listeners are taken out while they are notified, so that they may register new listeners.
+        let mut listeners = self.listeners.take();
+        for listener in listeners.iter_mut() {
+            listener(clocks);
+        }
+        let mut registered = self.listeners.borrow_mut();
+        listeners.append(&mut registered);
+        *registered = listeners;
+    }
+
+    /// Enter Stop mode while waiting for `wakeup` to complete, see `Pwr::stop()`,
+    /// and restore the clock tree that was last applied by `apply()` when woken up.
+    /// Note that the plli2s and the pllsai are not restored.
+    pub async fn stop<T>(
+        &self,
+        pwr: &Pwr,
+        flash: &Flash,
+        scb_scr: &scb::Scr<Srt>,
+        mode: StopMode,
+        wakeup: impl Future<Output = T>,
+    ) -> (T, Option<ConfiguredClockTree>) {
+        let output = pwr.stop(scb_scr, mode, wakeup).await;
+
+        // Sysclk is now sourced by the HSI and the HSE and the plls are disabled,
+        // which is the same state as when the tree was first applied on boot.
+        let clocks = match self.applied.take() {
+            Some(tree) => Some(self.apply(tree, pwr, flash).await),
+            None => None,
+        };
+        (output, clocks)
+    }
+
+    /// Select the sysclk source and wait for the switch to complete.
+    fn switch_sysclk(
+        &self,
+        sysclk: SysClk,
+        hsi: ConfiguredClk<HsiClk>,
+        hse: Option<ConfiguredClk<HseClk>>,
+        pll: Option<ConfiguredClk<Pll>>,
+    ) {
+        let sws = match sysclk.mux {
+            signal @ SysClkMuxSignal::Hsi(_) => {
+                self.select(signal, hsi);
+                0b00
+            }
+            signal @ SysClkMuxSignal::Hse(_) => {
+                self.select(signal, hse.unwrap());
+                0b01
+            }
+            signal @ SysClkMuxSignal::Pll(_) => {
+                self.select(signal, pll.unwrap().p());
+                0b10
+            }
+        };
+        while self.rcc.rcc_cfgr.load().sws() != sws {}
+    }
+
+    /// Disable the main pll and wait for it to stop.
+    fn disable_pll(&self) {
+        self.rcc.rcc_cr.modify(|r| r.clear_pllon());
+        while self.rcc.rcc_cr.load().pllrdy() {}
+    }
+
+    /// Get whether the HSE is used outside the applied clock tree,
+    /// i.e. by the plli2s or the pllsai, by the rtc, by a clock output, or by the clock security system.
+    fn is_hse_used(&self) -> bool {
+        let cr = self.rcc.rcc_cr.load();
+        #[cfg(any(
+            stm32_mcu = "stm32f427",
+            stm32_mcu = "stm32f429",
+            stm32_mcu = "stm32f437",
+            stm32_mcu = "stm32f439",
+            stm32_mcu = "stm32f446",
+            stm32_mcu = "stm32f469",
+            stm32_mcu = "stm32f479",
+        ))]
+        if (cr.plli2son() || cr.pllsaion()) && self.rcc.rcc_pllcfgr.load().pllsrc() {
+            return true;
+        }
+        let cfgr = self.rcc.rcc_cfgr.load();
+        cr.csson()
+            || self.rcc.rcc_bdcr.load().rtcsel() == 0b11
+            || cfgr.mco1() == 0b10
+            || cfgr.mco2() == 0b10
+    }
+
+    /// Disable the HSE and wait for it to stop.
+    fn disable_hse(&self) {
+        self.rcc.rcc_cr.modify(|r| r.clear_hseon());
+        while self.rcc.rcc_cr.load().hserdy() {}
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    clktree::*,
+    rcc::{hpre_div, ppre_div},
+    traits::HClkExt,
+    Flash, Rcc,
+};
+use core::fmt;
+use drone_cortexm::{reg::prelude::*, thr::prelude::*};
+
+/// The pll settings.
+#[derive(Copy, Clone, PartialEq)]
+pub struct PllSettings {
+    /// The pll source, i.e. "HSI" or "HSE".
+    pub src: &'static str,
+    pub m: u32,
+    pub n: u32,
+    pub p: u32,
+    pub q: u32,
+}
+
+/// The core clock tree settings, either as described by a `ClockTree` or as read back from the registers.
+#[derive(Copy, Clone, PartialEq)]
+pub struct ClockTreeSettings {
+    /// The sysclk source, i.e. "HSI", "HSE", or "PLL_P".
+    pub sysclk: &'static str,
+    /// The main pll settings, if the pll is enabled.
+    pub pll: Option<PllSettings>,
+    pub hpre: u32,
+    pub ppre1: u32,
+    pub ppre2: u32,
+    /// The number of flash wait states.
+    pub latency: u32,
+}
+
+impl ClockTreeSettings {
+    /// Get the settings described by a clock tree.
+    pub fn from_tree(tree: &ClockTree) -> ClockTreeSettings {
+        let sysclk = match tree.sysclk().mux {
+            SysClkMuxSignal::Hsi(_) => "HSI",
+            SysClkMuxSignal::Hse(_) => "HSE",
+            SysClkMuxSignal::Pll(_) => "PLL_P",
+        };
+        let pll = tree.pll.map(|pll| PllSettings {
+            src: match pll.vco.src.mux {
+                PllSrcMuxSignal::Hsi(_) => "HSI",
+                PllSrcMuxSignal::Hse(_) => "HSE",
+            },
+            m: pll.vco.src.m,
+            n: pll.vco.n,
+            p: pll.p.div,
+            q: pll.q.div,
+        });
+        ClockTreeSettings {
+            sysclk,
+            pll,
+            hpre: tree.hclk().hpre,
+            ppre1: tree.pclk1.ppre1,
+            ppre2: tree.pclk2.ppre2,
+            latency: tree.hclk().get_wait_states(tree.voltage),
+        }
+    }
+}
+
+/// A comparison of the settings described by a clock tree with the settings read back from the registers.
+pub struct ClockTreeComparison {
+    pub expected: ClockTreeSettings,
+    pub actual: ClockTreeSettings,
+}
+
+impl ClockTreeComparison {
+    /// Get whether the registers match the clock tree.
+    pub fn is_match(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+impl fmt::Display for ClockTreeComparison {
+    /// Format one setting per line, marking the settings where the registers differ from the clock tree.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (expected, actual) = (&self.expected, &self.actual);
+        match (expected.pll, actual.pll) {
+            (Some(expected), Some(actual)) => {
+                line(f, "PLLSRC", expected.src, actual.src)?;
+                line(f, "PLLM", expected.m, actual.m)?;
+                line(f, "PLLN", expected.n, actual.n)?;
+                line(f, "PLLP", expected.p, actual.p)?;
+                line(f, "PLLQ", expected.q, actual.q)?;
+            }
+            (expected, actual) => {
+                let state = |pll: Option<PllSettings>| if pll.is_some() { "on" } else { "off" };
+                line(f, "PLL", state(expected), state(actual))?;
+            }
+        }
+        line(f, "SW", expected.sysclk, actual.sysclk)?;
+        line(f, "HPRE", expected.hpre, actual.hpre)?;
+        line(f, "PPRE1", expected.ppre1, actual.ppre1)?;
+        line(f, "PPRE2", expected.ppre2, actual.ppre2)?;
+        line(f, "LATENCY", expected.latency, actual.latency)
+    }
+}
+
+fn line<T: fmt::Display + PartialEq>(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    expected: T,
+    actual: T,
+) -> fmt::Result {
+    if expected == actual {
+        writeln!(f, "{}: {}", name, actual)
+    } else {
+        writeln!(f, "{}: {} but expected {} (MISMATCH)", name, actual, expected)
+    }
+}
+
+impl<RccInt: IntToken> Rcc<RccInt> {
+    /// Read the clock tree settings back from RCC_CFGR, RCC_PLLCFGR, and FLASH_ACR.
+    pub fn settings(&self, flash: &Flash) -> ClockTreeSettings {
+        let cr = self.rcc.rcc_cr.load();
+        let cfgr = self.rcc.rcc_cfgr.load();
+        let pllcfgr = self.rcc.rcc_pllcfgr.load();
+        let sysclk = match cfgr.sws() {
+            0b00 => "HSI",
+            0b01 => "HSE",
+            _ => "PLL_P",
+        };
+        let pll = if cr.pllon() {
+            Some(PllSettings {
+                src: if pllcfgr.pllsrc() { "HSE" } else { "HSI" },
+                m: pllcfgr.pllm(),
+                n: pllcfgr.plln(),
+                p: (pllcfgr.pllp() + 1) * 2,
+                q: pllcfgr.pllq(),
+            })
+        } else {
+            None
+        };
+        ClockTreeSettings {
+            sysclk,
+            pll,
+            hpre: hpre_div(cfgr.hpre()),
+            ppre1: ppre_div(cfgr.ppre1()),
+            ppre2: ppre_div(cfgr.ppre2()),
+            latency: flash.latency(),
+        }
+    }
+
+    /// Compare the clock tree settings in the registers with a clock tree, e.g. the one passed to `apply()`.
+    pub fn compare(&self, tree: &ClockTree, flash: &Flash) -> ClockTreeComparison {
+        ClockTreeComparison {
+            expected: ClockTreeSettings::from_tree(tree),
+            actual: self.settings(flash),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::Rcc;
+use drone_cortexm::{reg::prelude::*, thr::prelude::*};
+
+/// The reset flags latched by the RCC since they were last cleared.
+/// Note that several flags may be set at once, e.g. a power-on reset also sets the pin and BOR reset flags.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ResetCause {
+    /// NRST pin reset.
+    pub pin: bool,
+    /// Power-on or power-down reset (POR/PDR).
+    pub por: bool,
+    /// Brown-out reset (BOR), a POR/PDR also sets this flag.
+    pub bor: bool,
+    /// Software reset.
+    pub software: bool,
+    /// Independent watchdog reset.
+    pub iwdg: bool,
+    /// Window watchdog reset.
+    pub wwdg: bool,
+    /// Low-power management reset, i.e. entering Stop or Standby when disallowed by the option bytes.
+    pub low_power: bool,
+}
+
+impl<RccInt: IntToken> Rcc<RccInt> {
+    /// Read the reset flags without clearing them.
+    pub fn reset_cause(&self) -> ResetCause {
+        let csr = self.rcc.rcc_csr.load();
+        ResetCause {
+            pin: csr.pinrstf(),
+            por: csr.porrstf(),
+            bor: csr.borrstf(),
+            software: csr.sftrstf(),
+            iwdg: csr.iwdgrstf(),
+            wwdg: csr.wwdgrstf(),
+            low_power: csr.lpwrrstf(),
+        }
+    }
+
+    /// Read and clear the reset flags, such that the flags reflect only the next reset.
+    pub fn take_reset_cause(&self) -> ResetCause {
+        let cause = self.reset_cause();
+        self.rcc.rcc_csr.modify(|r| r.set_rmvf());
+        cause
+    }
+}
@@ -0,0 +1,25 @@
+//! The device electronic signature.
+
+use core::ptr::read_volatile;
+
+/// Base address of the 96-bit unique device identifier.
+const UID_BASE: usize = 0x1FFF_7A10;
+/// Address of the flash size register.
+const FLASH_SIZE: usize = 0x1FFF_7A22;
+
+/// Get the 96-bit unique device identifier.
+pub fn unique_id() -> [u32; 3] {
+    let uid = UID_BASE as *const u32;
+    unsafe {
+        [
+            read_volatile(uid),
+            read_volatile(uid.add(1)),
+            read_volatile(uid.add(2)),
+        ]
+    }
+}
+
+/// Get the size of the flash memory in kilobytes.
+pub fn flash_size_kb() -> u32 {
+    unsafe { read_volatile(FLASH_SIZE as *const u16) as u32 }
+}
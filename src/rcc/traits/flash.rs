@@ -1,3 +1,5 @@
+use core::fmt;
+
 pub trait HClkExt {
     /// Get the number of flash access wait states for a given voltage range.
     fn get_wait_states(&self, voltage: VoltageRange) -> u32;
@@ -13,4 +15,15 @@ pub enum VoltageRange {
     LowVoltage,
     #[doc = "1.8V-2.1V"]
     UltraLowVoltage,
-}
\ No newline at end of file
+}
+
+impl fmt::Display for VoltageRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VoltageRange::HighVoltage => write!(f, "2.7V-3.6V"),
+            VoltageRange::MediumVoltage => write!(f, "2.4V-2.7V"),
+            VoltageRange::LowVoltage => write!(f, "2.1V-2.4V"),
+            VoltageRange::UltraLowVoltage => write!(f, "1.8V-2.1V"),
+        }
+    }
+}
@@ -1,4 +1,3 @@
-mod dma_ch;
 mod spi;
 
-pub(crate) use self::{dma_ch::DmaChDiverged, spi::SpiDiverged};
+pub(crate) use self::spi::SpiDiverged;
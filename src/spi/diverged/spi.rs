@@ -8,7 +8,7 @@ pub(crate) struct SpiDiverged<Spi: SpiMap> {
     pub(crate) rcc_bussmenr_spismen: Spi::SRccBussmenrSpismen,
     pub(crate) spi_sr: Spi::CSpiSr,
     pub(crate) spi_dr: Spi::SSpiDr,
-    pub(crate) spi_cr1: Spi::SSpiCr1,
+    pub(crate) spi_cr1: Spi::CSpiCr1,
     pub(crate) spi_cr2: Spi::SSpiCr2,
     pub(crate) spi_crcpr: Spi::SSpiCrcpr,
     pub(crate) spi_rxcrcr: Spi::SSpiRxcrcr,
@@ -39,7 +39,7 @@ impl<Spi: SpiMap> From<SpiPeriph<Spi>> for SpiDiverged<Spi> {
             rcc_bussmenr_spismen,
             spi_sr: spi_sr.into_copy(),
             spi_dr,
-            spi_cr1,
+            spi_cr1: spi_cr1.into_copy(),
             spi_cr2,
             spi_crcpr,
             spi_rxcrcr,
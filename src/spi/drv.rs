@@ -12,6 +12,7 @@ pub struct SpiDrv<Spi: SpiMap, SpiInt: IntToken, Clk: PClkToken> {
     pub(crate) spi: SpiDiverged<Spi>,
     spi_int: SpiInt,
     clk: PhantomData<Clk>,
+    baud_rate: BaudRate,
 }
 
 impl<Spi: SpiMap, SpiInt: IntToken, Clk: PClkToken> SpiDrv<Spi, SpiInt, Clk> {
@@ -20,11 +21,22 @@ impl<Spi: SpiMap, SpiInt: IntToken, Clk: PClkToken> SpiDrv<Spi, SpiInt, Clk> {
             spi: setup.spi.into(),
             spi_int: setup.spi_int,
             clk: PhantomData,
+            baud_rate: setup.baud_rate,
         };
         drv.init_spi(&setup.clk, setup.baud_rate, setup.clk_pol, setup.first_bit);
         drv
     }
 
+    /// Get a listener that reprograms the baud rate prescaler when the spi clock is changed,
+    /// e.g. when it is called from a listener registered by `Rcc::on_apply()`.
+    pub fn clk_listener(&self) -> SpiClkListener<Spi, Clk> {
+        SpiClkListener {
+            spi_cr1: self.spi.spi_cr1,
+            clk: PhantomData,
+            baud_rate: self.baud_rate,
+        }
+    }
+
     fn init_spi(
         &mut self,
         clk: &ConfiguredClk<Clk>,
@@ -71,6 +83,29 @@ impl<Spi: SpiMap, SpiInt: IntToken, Clk: PClkToken> SpiDrv<Spi, SpiInt, Clk> {
     }
 }
 
+/// Spi baud rate prescaler updater.
+pub struct SpiClkListener<Spi: SpiMap, Clk: PClkToken> {
+    spi_cr1: Spi::CSpiCr1,
+    clk: PhantomData<Clk>,
+    baud_rate: BaudRate,
+}
+
+impl<Spi: SpiMap, Clk: PClkToken> SpiClkListener<Spi, Clk> {
+    /// Reprogram the baud rate prescaler for a new spi clock frequency.
+    /// The spi is disabled while the prescaler is changed, so it should only be called while no transfer is in progress.
+    pub fn set_clk(&self, clk: ConfiguredClk<Clk>) {
+        let cr1 = self.spi_cr1.load_val();
+        let enabled = self.spi_cr1.spe().read(&cr1);
+        self.spi_cr1.modify_reg(|r, v| r.spe().clear(v));
+        self.spi_cr1.modify_reg(|r, v| {
+            r.br().write(v, spi_br(&clk, self.baud_rate));
+            if enabled {
+                r.spe().set(v);
+            }
+        });
+    }
+}
+
 pub trait IntoMaster<
     Spi: SpiMap,
     DmaRxCh: DmaChMap,
@@ -10,7 +10,7 @@ mod master;
 mod setup;
 mod pins;
 
-pub use self::drv::SpiDrv;
+pub use self::drv::{SpiClkListener, SpiDrv};
 pub use self::master::SpiMasterDrv;
 pub use self::setup::{SpiSetup, BaudRate, Prescaler, ClkPol, FirstBit};
 pub use drone_stm32_map::periph::spi::SpiMap;
@@ -1,10 +1,12 @@
-use crate::diverged::{DmaChDiverged, SpiDiverged};
-use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
+use crate::diverged::SpiDiverged;
+use drone_cortexm::{reg::prelude::*, thr::prelude::*};
 use drone_stm32_map::periph::{
     dma::ch::DmaChMap,
     spi::{traits::*, SpiMap},
 };
-use drone_stm32f4_dma_drv::{DmaChCfg, DmaStChToken};
+use drone_stm32f4_dma_drv::{
+    DmaChCfg, DmaDataSize, DmaDir, DmaStChToken, DmaStream, DmaStreamSetup,
+};
 
 pub struct SpiMasterDrv<
     Spi: SpiMap,
@@ -14,10 +16,8 @@ pub struct SpiMasterDrv<
     DmaTxInt: IntToken,
 > {
     pub(crate) spi: SpiDiverged<Spi>,
-    pub(crate) dma_rx: DmaChDiverged<DmaRx>,
-    pub(crate) dma_rx_int: DmaRxInt,
-    pub(crate) dma_tx: DmaChDiverged<DmaTx>,
-    pub(crate) dma_tx_int: DmaTxInt,
+    pub(crate) dma_rx: DmaStream<DmaRx, DmaRxInt>,
+    pub(crate) dma_tx: DmaStream<DmaTx, DmaTxInt>,
 }
 
 impl<
@@ -33,27 +33,31 @@ impl<
         miso_cfg: DmaChCfg<DmaRx, DmaRxStCh, DmaRxInt>,
         mosi_cfg: DmaChCfg<DmaTx, DmaTxStCh, DmaTxInt>,
     ) -> Self {
-        let DmaChCfg {
-            dma_ch: dma_rx,
-            dma_int: dma_rx_int,
-            dma_pl: dma_rx_pl,
-            ..
-        } = miso_cfg;
-        let DmaChCfg {
-            dma_ch: dma_tx,
-            dma_int: dma_tx_int,
-            dma_pl: dma_tx_pl,
-            ..
-        } = mosi_cfg;
+        let spi_dr = spi.spi_dr.as_mut_ptr() as u32;
+        let rx_setup = DmaStreamSetup::new(DmaDir::PeriphToMem, spi_dr);
+        let tx_setup = DmaStreamSetup {
+            tcie: false,
+            ..DmaStreamSetup::new(DmaDir::MemToPeriph, spi_dr)
+        };
         let master = Self {
             spi,
-            dma_rx: dma_rx.into(),
-            dma_rx_int,
-            dma_tx: dma_tx.into(),
-            dma_tx_int,
+            dma_rx: DmaStream::init(miso_cfg, rx_setup),
+            dma_tx: DmaStream::init(mosi_cfg, tx_setup),
         };
 
+        // The frame format is either 8-bit or 16-bit and is shared by the rx and tx streams.
+        let frame = master.dma_rx.data_size();
+        assert!(frame != DmaDataSize::Word);
+        assert!(master.dma_tx.data_size() == frame);
+
         master.spi.spi_cr1.modify_reg(|r, v| {
+            // Data frame format.
+            if frame == DmaDataSize::HalfWord {
+                r.dff().set(v);
+            } else {
+                r.dff().clear(v);
+            }
+
             // Master configuration.
             r.mstr().set(v);
 
@@ -68,16 +72,6 @@ impl<
             r.spe().set(v);
         });
 
-        master
-            .dma_rx
-            .init_dma_rx(master.spi.spi_dr.as_mut_ptr() as u32, DmaRxStCh::NUM, dma_rx_pl);
-        master.dma_rx.panic_on_err(master.dma_rx_int);
-
-        master
-            .dma_tx
-            .init_dma_tx(master.spi.spi_dr.as_mut_ptr() as u32, DmaTxStCh::NUM, dma_tx_pl);
-        master.dma_tx.panic_on_err(master.dma_tx_int);
-
         master
     }
 
@@ -91,8 +85,8 @@ impl<
 
         unsafe {
             // Setup DMA transfer parameters.
-            self.dma_rx.setup_dummy_stream(buf.len());
-            self.dma_tx.setup_stream(buf);
+            self.dma_rx.start_dummy(buf.len());
+            self.dma_tx.start_tx(buf);
 
             self.xfer_impl().await;
         }
@@ -107,8 +101,8 @@ impl<
         self.wait_for_idle();
 
         unsafe {
-            self.dma_rx.setup_stream(buf);
-            self.dma_tx.setup_dummy_stream(buf.len());
+            self.dma_rx.start_rx(buf);
+            self.dma_tx.start_dummy(buf.len());
 
             self.xfer_impl().await;
         }
@@ -125,8 +119,8 @@ impl<
         self.wait_for_idle();
 
         unsafe {
-            self.dma_rx.setup_stream(rx_buf);
-            self.dma_tx.setup_stream(tx_buf);
+            self.dma_rx.start_rx(rx_buf);
+            self.dma_tx.start_tx(tx_buf);
 
             self.xfer_impl().await;
         }
@@ -145,21 +139,7 @@ impl<
     async unsafe fn xfer_impl(&mut self) {
         // Start listen for rx dma transfer to complete.
         // Rx completion is guaranteed to always happen after tx has completed.
-        let dma_isr_tcif = self.dma_rx.dma_isr_tcif;
-        let dma_ifcr_ctcif = self.dma_rx.dma_ifcr_ctcif;
-        let dma_rx_tc = self.dma_rx_int.add_future(fib::new_fn(move || {
-            if dma_isr_tcif.read_bit() {
-                // Clear transfer completed interrupt flag.
-                dma_ifcr_ctcif.set_bit();
-                fib::Complete(())
-            } else {
-                fib::Yielded(())
-            }
-        }));
-
-        // Clear any outstanding fifo error interrupt flag by settings its clear register.
-        self.dma_rx.dma_ifcr_cfeif.set_bit();
-        self.dma_tx.dma_ifcr_cfeif.set_bit();
+        let dma_rx_tc = self.dma_rx.transfer_complete();
 
         // Start transfer on DMA channel.
         self.spi.spi_cr2.modify_reg(|r, v| {
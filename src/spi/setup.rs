@@ -30,6 +30,7 @@ pub trait NewSpiSetup<Spi: SpiMap, SpiInt: IntToken, Clk: PClkToken> {
     ) -> Self;
 }
 
+#[derive(Copy, Clone)]
 pub enum BaudRate {
     Max(u32),
     Prescaler(Prescaler),
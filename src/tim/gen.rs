@@ -10,7 +10,7 @@ use crate::{
     shared::DontCare, traits::*, GeneralTimChDrv, GeneralTimCntDrv, GeneralTimOvfDrv, TimFreq,
 };
 
-pub struct GeneralTimSetup<Tim: GeneralTimMap, Int: IntToken, Clk: PClkToken> {
+pub struct GeneralTimSetup<Tim: GeneralTimMap, Int: IntToken, Clk: TimClkToken> {
     /// The timer peripheral.
     pub tim: GeneralTimPeriph<Tim>,
     /// The timer interrupt.
@@ -25,7 +25,7 @@ pub struct GeneralTimSetup<Tim: GeneralTimMap, Int: IntToken, Clk: PClkToken> {
     pub debug_stop: bool,
 }
 
-pub trait NewGeneralTimSetup<Tim: GeneralTimMap, Int: IntToken, Clk: PClkToken> {
+pub trait NewGeneralTimSetup<Tim: GeneralTimMap, Int: IntToken, Clk: TimClkToken> {
     /// Create a new tim setup with sensible defaults.
     fn new(
         tim: GeneralTimPeriph<Tim>,
@@ -63,7 +63,7 @@ macro_rules! general_tim_setup {
 pub struct GeneralTimCfg<
     Tim: GeneralTimMap,
     Int: IntToken,
-    Clk: PClkToken,
+    Clk: TimClkToken,
     Dir: Send + Sync,
     Link,
     Ch1Mode,
@@ -104,7 +104,7 @@ pub struct GeneralTimDiverged<Tim: GeneralTimMap> {
 impl<
         Tim: GeneralTimMap,
         Int: IntToken,
-        Clk: PClkToken,
+        Clk: TimClkToken,
         Dir: Send + Sync,
         Link,
         Ch1Mode,
@@ -143,7 +143,7 @@ impl<
     }
 }
 
-impl<Tim: GeneralTimMap, Int: IntToken, Clk: PClkToken>
+impl<Tim: GeneralTimMap, Int: IntToken, Clk: TimClkToken>
     GeneralTimCfg<Tim, Int, Clk, DirCountUp, DefaultLink, DontCare, DontCare, DontCare, DontCare>
 {
     /// Initialize a general timer with the correct prescaler.
@@ -212,9 +212,9 @@ impl<Tim: GeneralTimMap, Int: IntToken, Clk: PClkToken>
     }
 
     fn tim_psc(clk: &ConfiguredClk<Clk>, freq: TimFreq) -> u16 {
-        let f_pclk_timer = clk.freq() * 2; // The PCLK is multipled by 2 before it enters the timer, see the clock tree for reference.
+        let f_timclk = clk.freq();
         match freq {
-            TimFreq::Nominal(freq) => (((f_pclk_timer + (freq / 2)) / freq) - 1) as u16,
+            TimFreq::Nominal(freq) => (((f_timclk + (freq / 2)) / freq) - 1) as u16,
             TimFreq::Prescaler(prescaler) => prescaler - 1,
         }
     }
@@ -223,7 +223,7 @@ impl<Tim: GeneralTimMap, Int: IntToken, Clk: PClkToken>
 impl<
         Tim: GeneralTimMap + TimCr1Dir + TimCr1Cms,
         Int: IntToken,
-        Clk: PClkToken,
+        Clk: TimClkToken,
         Dir: Send + Sync,
         Link,
         Ch1Mode,
@@ -258,7 +258,7 @@ impl<
 impl<
         Tim: GeneralTimMap + TimCr1Dir + TimCr1Cms + TimCr2 + TimSmcr,
         Int: IntToken,
-        Clk: PClkToken,
+        Clk: TimClkToken,
         Dir: Send + Sync,
         Ch1Mode,
         Ch2Mode,
@@ -301,7 +301,7 @@ pub(crate) fn slave_of<Tim: GeneralTimMap + TimSmcr>(
 impl<
         Tim: GeneralTimMap,
         Int: IntToken,
-        Clk: PClkToken,
+        Clk: TimClkToken,
         Dir: Send + Sync,
         Link,
         Ch1Mode,
@@ -333,7 +333,7 @@ impl<
 pub trait ConfigureTimCh1<
     Tim: GeneralTimMap,
     Int: IntToken,
-    Clk: PClkToken,
+    Clk: TimClkToken,
     Dir: Send + Sync,
     Link,
     Ch2Mode,
@@ -355,7 +355,7 @@ pub trait ConfigureTimCh1<
 pub trait ConfigureTimCh2<
     Tim: GeneralTimMap,
     Int: IntToken,
-    Clk: PClkToken,
+    Clk: TimClkToken,
     Dir: Send + Sync,
     Link,
     Ch1Mode,
@@ -377,7 +377,7 @@ pub trait ConfigureTimCh2<
 pub trait ConfigureTimCh3<
     Tim: GeneralTimMap,
     Int: IntToken,
-    Clk: PClkToken,
+    Clk: TimClkToken,
     Dir: Send + Sync,
     Link,
     Ch1Mode,
@@ -399,7 +399,7 @@ pub trait ConfigureTimCh3<
 pub trait ConfigureTimCh4<
     Tim: GeneralTimMap,
     Int: IntToken,
-    Clk: PClkToken,
+    Clk: TimClkToken,
     Dir: Send + Sync,
     Link,
     Ch1Mode,
@@ -423,7 +423,7 @@ macro_rules! general_tim_ch {
     ($tim_ch:ident; $trait_name:ident<$tim:ident, ..., $($modes:ident),+>.$fn_name:ident; $($ch_fields:ident),+ -> GeneralTimChDrv<$($out_modes:ident),+> for GeneralTimCfg<$($for_modes:ident),+>) => {
         impl<
             Int: drone_cortexm::thr::IntToken,
-            Clk: drone_stm32f4_rcc_drv::clktree::PClkToken,
+            Clk: drone_stm32f4_rcc_drv::clktree::TimClkToken,
             Dir: Send + Sync,
             Link,
             $($modes),+>
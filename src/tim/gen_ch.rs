@@ -169,8 +169,10 @@ macro_rules! general_tim_channel {
     };
 }
 
-impl<Tim: GeneralTimMap, Int: IntToken, Ch: GeneralTimCh<Tim>, Pin: GpioPinMap, Af: PinAf, PinType: PinTypeMap, PinPull: PinPullMap, Sel: InputSelection>
-    GeneralTimChDrv<Tim, Int, Ch, InputCaptureMode<Pin, Af, PinType, PinPull, Sel>>
+impl<Tim: GeneralTimMap, Int: IntToken, Ch: GeneralTimCh<Tim>, Mode: CaptureMode>
+    GeneralTimChDrv<Tim, Int, Ch, Mode>
+where
+    Self: CaptureStop,
 {
     fn capture_stream<Item>(
         &mut self,
@@ -207,16 +209,8 @@ impl<Tim: GeneralTimMap, Int: IntToken, Ch: GeneralTimCh<Tim>, Pin: GpioPinMap,
     }
 }
 
-impl<
-        Tim: GeneralTimMap,
-        Int: IntToken,
-        Ch: GeneralTimCh<Tim>,
-        Pin: GpioPinMap,
-        Af: PinAf,
-        PinType: PinTypeMap,
-        PinPull: PinPullMap,
-        Sel: InputSelection,
-    > TimerCaptureCh for GeneralTimChDrv<Tim, Int, Ch, InputCaptureMode<Pin, Af, PinType, PinPull, Sel>>
+impl<Tim: GeneralTimMap, Int: IntToken, Ch: GeneralTimCh<Tim>, Mode: CaptureMode> TimerCaptureCh
+    for GeneralTimChDrv<Tim, Int, Ch, Mode>
 {
     type Stop = Self;
 
@@ -259,17 +253,8 @@ impl<
     }
 }
 
-impl<
-        Tim: GeneralTimMap,
-        Int: IntToken,
-        Ch: GeneralTimCh<Tim>,
-        Pin: GpioPinMap,
-        Af: PinAf,
-        PinType: PinTypeMap,
-        PinPull: PinPullMap,
-        Sel: InputSelection,
-    > CaptureStop
-    for GeneralTimChDrv<Tim, Int, Ch, InputCaptureMode<Pin, Af, PinType, PinPull, Sel>>
+impl<Tim: GeneralTimMap, Int: IntToken, Ch: GeneralTimCh<Tim>, Mode: CaptureMode> CaptureStop
+    for GeneralTimChDrv<Tim, Int, Ch, Mode>
 {
     fn stop(&mut self) {
         Ch::clear_ccie(self.tim.tim_dier);
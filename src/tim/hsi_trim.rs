@@ -0,0 +1,102 @@
+use drone_cortexm::thr::prelude::*;
+use drone_stm32f4_rcc_drv::{clktree::HSI_TRIM_MAX, Rcc};
+use futures::StreamExt;
+
+use crate::traits::*;
+
+/// HSI trimming setup.
+pub struct HsiTrimSetup {
+    /// The nominal timer counter frequency, i.e. the counter frequency if the HSI runs at exactly 16MHz.
+    pub f_cnt: u32,
+    /// The frequency of the reference clock that is captured, e.g. 32768Hz for the LSE.
+    pub f_ref: u32,
+    /// The timer auto-reload value.
+    pub arr: u32,
+    /// The number of reference clock periods in each measurement.
+    pub periods: u32,
+}
+
+impl HsiTrimSetup {
+    /// Create a new setup for trimming against a reference clock with sensible defaults,
+    /// e.g. with `f_ref` = 32768Hz for the LSE or the HSE frequency divided by RTCPRE for HSE_RTC.
+    pub fn new(f_cnt: u32, f_ref: u32, arr: u32) -> Self {
+        Self {
+            f_cnt,
+            f_ref,
+            arr,
+            periods: 256,
+        }
+    }
+}
+
+/// The result of an HSI trimming.
+#[derive(Copy, Clone, Debug)]
+pub struct HsiTrim {
+    /// The selected HSITRIM value.
+    pub trim: u32,
+    /// The measured HSI frequency error in ppm with the selected trimming value.
+    pub error_ppm: i32,
+}
+
+/// Trim the HSI against a reference clock captured by a timer channel,
+/// e.g. the LSE on TIM5 channel 4 or HSE_RTC on TIM11 channel 1.
+///
+/// HSITRIM is adjusted one step at a time in the direction that reduces the measured error,
+/// until the error no longer improves or the trimming range is exhausted.
+/// The timer must be clocked (through the pll) by the HSI.
+pub async fn trim_hsi<RccInt: IntToken, Capture: TimerCaptureCh>(
+    rcc: &Rcc<RccInt>,
+    capture: &mut Capture,
+    setup: &HsiTrimSetup,
+) -> HsiTrim {
+    let mut trim = rcc.hsi_trim();
+    let mut error_ppm = measure(capture, setup).await;
+    loop {
+        let next = if error_ppm > 0 && trim > 0 {
+            trim - 1
+        } else if error_ppm < 0 && trim < HSI_TRIM_MAX {
+            trim + 1
+        } else {
+            break;
+        };
+        rcc.set_hsi_trim(next);
+        let next_error_ppm = measure(capture, setup).await;
+        if next_error_ppm.abs() >= error_ppm.abs() {
+            rcc.set_hsi_trim(trim);
+            break;
+        }
+        trim = next;
+        error_ppm = next_error_ppm;
+    }
+    HsiTrim { trim, error_ppm }
+}
+
+/// Measure the HSI frequency error in ppm by counting the timer ticks in a number of reference clock periods.
+/// The measurement is repeated if any capture is lost.
+async fn measure<Capture: TimerCaptureCh>(capture: &mut Capture, setup: &HsiTrimSetup) -> i32 {
+    let ticks = loop {
+        if let Some(ticks) = count_ticks(capture, setup).await {
+            break ticks;
+        }
+    };
+    let expected = setup.f_cnt as u64 * setup.periods as u64 / setup.f_ref as u64;
+    ((ticks as i64 - expected as i64) * 1_000_000 / expected as i64) as i32
+}
+
+/// Count the timer ticks in a number of reference clock periods,
+/// or get `None` if the capture stream overflowed, in which case the ticks span an unknown number of periods.
+async fn count_ticks<Capture: TimerCaptureCh>(capture: &mut Capture, setup: &HsiTrimSetup) -> Option<u64> {
+    let mut stream = capture.try_stream(16, TimerCapturePolarity::RisingEdge);
+    let mut last = stream.next().await?.ok()?;
+    let mut ticks = 0u64;
+    for _ in 0..setup.periods {
+        let capture = stream.next().await?.ok()?;
+        ticks += if capture >= last {
+            (capture - last) as u64
+        } else {
+            capture as u64 + setup.arr as u64 + 1 - last as u64
+        };
+        last = capture;
+    }
+    Some(ticks)
+}
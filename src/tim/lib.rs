@@ -7,7 +7,9 @@ mod gen;
 mod gen_ch;
 mod gen_cnt;
 mod gen_ovf;
+mod hsi_trim;
 mod mappings;
+mod periph;
 mod shared;
 mod traits;
 
@@ -18,6 +20,8 @@ pub use self::gen::{
 pub use self::gen_ch::{GeneralTimCh, GeneralTimChDrv, IntoPinInputCaptureMode};
 pub use self::gen_cnt::GeneralTimCntDrv;
 pub use self::gen_ovf::GeneralTimOvfDrv;
+pub use self::hsi_trim::{trim_hsi, HsiTrim, HsiTrimSetup};
+pub use self::periph::*;
 pub use self::shared::TimFreq;
 pub use self::traits::*;
 pub use drone_stm32_map::periph::tim::general::GeneralTimMap;
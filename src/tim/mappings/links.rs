@@ -3,13 +3,13 @@ use core::marker::PhantomData;
 use drone_cortexm::thr::IntToken;
 use drone_stm32_map::periph::tim::advanced::*;
 use drone_stm32_map::periph::tim::general::*;
-use drone_stm32f4_rcc_drv::clktree::PClkToken;
+use drone_stm32f4_rcc_drv::clktree::TimClkToken;
 
 macro_rules! timer_link {
     ($type_type:ident<$slave_tim:ident>; $itr0_tim:ident, $itr1_tim:ident, $itr2_tim:ident, $itr3_tim:ident) => {
         impl<
                 Int: IntToken,
-                Clk: PClkToken,
+                Clk: TimClkToken,
                 Dir: Send + Sync,
                 Ch1Mode,
                 Ch2Mode,
@@ -50,7 +50,7 @@ macro_rules! timer_link {
         }
         impl<
                 Int: IntToken,
-                Clk: PClkToken,
+                Clk: TimClkToken,
                 Dir: Send + Sync,
                 Ch1Mode,
                 Ch2Mode,
@@ -91,7 +91,7 @@ macro_rules! timer_link {
         }
         impl<
                 Int: IntToken,
-                Clk: PClkToken,
+                Clk: TimClkToken,
                 Dir: Send + Sync,
                 Ch1Mode,
                 Ch2Mode,
@@ -132,7 +132,7 @@ macro_rules! timer_link {
         }
         impl<
                 Int: IntToken,
-                Clk: PClkToken,
+                Clk: TimClkToken,
                 Dir: Send + Sync,
                 Ch1Mode,
                 Ch2Mode,
@@ -1,4 +1,4 @@
 // use drone_stm32_map::periph::tim::general::Tim1;
-// use drone_stm32f4_rcc_drv::clktree::PClk2;
+// use drone_stm32f4_rcc_drv::clktree::TimClk2;
 
-// general_tim_setup!(Tim1, PClk2);
+// general_tim_setup!(Tim1, TimClk2);
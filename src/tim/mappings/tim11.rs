@@ -1,5 +1,19 @@
-use crate::general_tim_setup;
+use crate::{
+    general_tim_setup, shared::DontCare, DirectSelection, GeneralTimCh, GeneralTimChDrv,
+    HseRtcInput, InternalCaptureMode, Tim11RmpPeriph, TimCh1,
+};
+use drone_cortexm::{reg::prelude::*, thr::prelude::*};
 use drone_stm32_map::periph::tim::general::Tim11;
-use drone_stm32f4_rcc_drv::clktree::PClk2;
+use drone_stm32f4_rcc_drv::clktree::TimClk2;
 
-general_tim_setup!(Tim11, PClk2);
+general_tim_setup!(Tim11, TimClk2);
+
+impl<Int: IntToken> GeneralTimChDrv<Tim11, Int, TimCh1, DontCare> {
+    /// Configure the channel as Input/Capture of the HSE_RTC clock, i.e. the HSE divided by RTCPRE.
+    pub fn into_hse_rtc_capture(self, rmp: Tim11RmpPeriph) -> GeneralTimChDrv<Tim11, Int, TimCh1, InternalCaptureMode<HseRtcInput>> {
+        // Remap the channel 1 input to HSE_RTC, i.e. TI1_RMP = 0b10, leaving the other bits untouched.
+        rmp.tim11_or_ti1_rmp.write_bits(0b10);
+        TimCh1::configure_input(&self.tim, DirectSelection);
+        GeneralTimChDrv::new(self.tim, self.tim_int, InternalCaptureMode::new())
+    }
+}
@@ -6,9 +6,9 @@ use crate::{
 use drone_stm32_map::periph::gpio::pin::*;
 use drone_stm32_map::periph::tim::general::Tim3;
 use drone_stm32f4_gpio_drv::PinAf2;
-use drone_stm32f4_rcc_drv::clktree::PClk1;
+use drone_stm32f4_rcc_drv::clktree::TimClk1;
 
-general_tim_setup!(Tim3, PClk1);
+general_tim_setup!(Tim3, TimClk1);
 
 general_tim_ch!(TimCh1; ConfigureTimCh1<Tim3, ..., ChMode2, ChMode3, ChMode4>.ch1; ch2, ch3, ch4 -> GeneralTimChDrv<ChMode, ChMode2, ChMode3, ChMode4> for GeneralTimCfg<DontCare, ChMode2, ChMode3, ChMode4>);
 general_tim_ch!(TimCh2; ConfigureTimCh2<Tim3, ..., ChMode1, ChMode3, ChMode4>.ch2; ch1, ch3, ch4 -> GeneralTimChDrv<ChMode1, ChMode, ChMode3, ChMode4> for GeneralTimCfg<ChMode1, DontCare, ChMode3, ChMode4>);
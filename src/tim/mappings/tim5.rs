@@ -1,20 +1,32 @@
 use crate::{
     general_tim_ch, general_tim_channel, general_tim_setup, shared::DontCare, ConfigureTimCh1,
-    ConfigureTimCh2, ConfigureTimCh3, ConfigureTimCh4, DirectSelection, GeneralTimChDrv,
-    IndirectSelection, TimCh1, TimCh2, TimCh3, TimCh4,
+    ConfigureTimCh2, ConfigureTimCh3, ConfigureTimCh4, DirectSelection, GeneralTimCh,
+    GeneralTimChDrv, IndirectSelection, InternalCaptureMode, LseInput, Tim5RmpPeriph, TimCh1,
+    TimCh2, TimCh3, TimCh4,
 };
+use drone_cortexm::{reg::prelude::*, thr::prelude::*};
 use drone_stm32_map::periph::gpio::pin::*;
 use drone_stm32_map::periph::tim::general::Tim5;
 use drone_stm32f4_gpio_drv::PinAf2;
-use drone_stm32f4_rcc_drv::clktree::PClk1;
+use drone_stm32f4_rcc_drv::clktree::TimClk1;
 
-general_tim_setup!(Tim5, PClk1);
+general_tim_setup!(Tim5, TimClk1);
 
 general_tim_ch!(TimCh1; ConfigureTimCh1<Tim5, ..., ChMode2, ChMode3, ChMode4>.ch1; ch2, ch3, ch4 -> GeneralTimChDrv<ChMode, ChMode2, ChMode3, ChMode4> for GeneralTimCfg<DontCare, ChMode2, ChMode3, ChMode4>);
 general_tim_ch!(TimCh2; ConfigureTimCh2<Tim5, ..., ChMode1, ChMode3, ChMode4>.ch2; ch1, ch3, ch4 -> GeneralTimChDrv<ChMode1, ChMode, ChMode3, ChMode4> for GeneralTimCfg<ChMode1, DontCare, ChMode3, ChMode4>);
 general_tim_ch!(TimCh3; ConfigureTimCh3<Tim5, ..., ChMode1, ChMode2, ChMode4>.ch3; ch1, ch2, ch4 -> GeneralTimChDrv<ChMode1, ChMode2, ChMode, ChMode4> for GeneralTimCfg<ChMode1, ChMode2, DontCare, ChMode4>);
 general_tim_ch!(TimCh4; ConfigureTimCh4<Tim5, ..., ChMode1, ChMode2, ChMode3>.ch4; ch1, ch2, ch3 -> GeneralTimChDrv<ChMode1, ChMode2, ChMode3, ChMode> for GeneralTimCfg<ChMode1, ChMode2, ChMode3, DontCare>);
 
+impl<Int: IntToken> GeneralTimChDrv<Tim5, Int, TimCh4, DontCare> {
+    /// Configure the channel as Input/Capture of the LSE clock.
+    pub fn into_lse_capture(self, rmp: Tim5RmpPeriph) -> GeneralTimChDrv<Tim5, Int, TimCh4, InternalCaptureMode<LseInput>> {
+        // Remap the channel 4 input to the LSE, i.e. TI4_RMP = 0b10, leaving the other remap bits untouched.
+        rmp.tim5_or_ti4_rmp.write_bits(0b10);
+        TimCh4::configure_input(&self.tim, DirectSelection);
+        GeneralTimChDrv::new(self.tim, self.tim_int, InternalCaptureMode::new())
+    }
+}
+
 general_tim_channel!(
     TimCh1<Tim5>, GpioA0<PinAf2> -> DirectSelection;
     TimCh1<Tim5>, GpioB6<PinAf2> -> DirectSelection;
@@ -1,4 +1,4 @@
 // use drone_stm32_map::periph::tim::general::Tim6;
-// use drone_stm32f4_rcc_drv::clktree::PClk1;
+// use drone_stm32f4_rcc_drv::clktree::TimClk1;
 
-// general_tim_setup!(Tim6, PClk1);
+// general_tim_setup!(Tim6, TimClk1);
@@ -0,0 +1,35 @@
+use drone_core::periph;
+
+periph::singular! {
+    /// Extracts TIM5 input remap register tokens.
+    pub macro periph_tim5_rmp;
+    /// TIM5 input remap peripheral, i.e. the channel 4 input selection.
+    pub struct Tim5RmpPeriph;
+    // Path prefix to reach registers.
+    drone_stm32_map::reg;
+    // Absolute path to the current module.
+    crate;
+
+    TIM5 {
+        OR {
+            TI4_RMP;
+        }
+    }
+}
+
+periph::singular! {
+    /// Extracts TIM11 input remap register tokens.
+    pub macro periph_tim11_rmp;
+    /// TIM11 input remap peripheral, i.e. the channel 1 input selection.
+    pub struct Tim11RmpPeriph;
+    // Path prefix to reach registers.
+    drone_stm32_map::reg;
+    // Absolute path to the current module.
+    crate;
+
+    TIM11 {
+        OR {
+            TI1_RMP;
+        }
+    }
+}
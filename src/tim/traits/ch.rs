@@ -33,6 +33,31 @@ impl<Pin: GpioPinMap, Af: PinAf, PinType: PinTypeMap, PinPull: PinPullMap, Sel:
     }
 }
 
+/// Timer Input Capture mode from an internal clock signal instead of a pin.
+pub struct InternalCaptureMode<Src: Send + Sync + 'static> {
+    src: PhantomData<Src>,
+}
+
+impl<Src: Send + Sync + 'static> InternalCaptureMode<Src> {
+    pub fn new() -> Self {
+        Self { src: PhantomData }
+    }
+}
+
+/// The LSE clock, internally routed to TIM5 channel 4.
+pub struct LseInput;
+
+/// The HSE_RTC clock, internally routed to TIM11 channel 1.
+pub struct HseRtcInput;
+
+/// A channel mode that captures an input.
+pub trait CaptureMode: Send + 'static {}
+impl<Pin: GpioPinMap, Af: PinAf, PinType: PinTypeMap, PinPull: PinPullMap, Sel: Send + Sync + 'static> CaptureMode
+    for InputCaptureMode<Pin, Af, PinType, PinPull, Sel>
+{
+}
+impl<Src: Send + Sync + 'static> CaptureMode for InternalCaptureMode<Src> {}
+
 /// Channel X maps directly to the input for channel X.
 pub struct DirectSelection;
 
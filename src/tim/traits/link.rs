@@ -1,7 +1,7 @@
 use core::marker::PhantomData;
 
 use drone_cortexm::thr::IntToken;
-use drone_stm32f4_rcc_drv::clktree::PClkToken;
+use drone_stm32f4_rcc_drv::clktree::TimClkToken;
 
 pub struct DefaultLink;
 pub struct MasterLink<MasterTim>(PhantomData<MasterTim>);
@@ -15,7 +15,7 @@ impl<MasterTim> LinkToken for SlaveLink<MasterTim> {}
 pub trait TimerLink<
     Tim,
     Int: IntToken,
-    Clk: PClkToken,
+    Clk: TimClkToken,
     Dir: Send + Sync,
     Ch1Mode,
     Ch2Mode,
@@ -14,6 +14,8 @@ pub struct UartDrv<Uart: UartMap, UartInt: IntToken, Clk: PClkToken> {
     pub(crate) uart: Arc<UartDiverged<Uart>>,
     pub(crate) uart_int: UartInt,
     clk: PhantomData<Clk>,
+    baud_rate: BaudRate,
+    oversampling: u32,
 }
 
 impl<Uart: UartMap, UartInt: IntToken, Clk: PClkToken> UartDrv<Uart, UartInt, Clk> {
@@ -36,11 +38,24 @@ impl<Uart: UartMap, UartInt: IntToken, Clk: PClkToken> UartDrv<Uart, UartInt, Cl
             uart: Arc::new(uart.into()),
             uart_int,
             clk: PhantomData,
+            baud_rate,
+            oversampling,
         };
         drv.init_uart(clk, baud_rate, data_bits, parity, stop_bits, oversampling);
         drv
     }
 
+    /// Get a listener that reprograms the baud rate divider when the uart clock is changed,
+    /// e.g. when it is called from a listener registered by `Rcc::on_apply()`.
+    pub fn clk_listener(&self) -> UartClkListener<Uart, Clk> {
+        UartClkListener {
+            uart: self.uart.clone(),
+            clk: PhantomData,
+            baud_rate: self.baud_rate,
+            oversampling: self.oversampling,
+        }
+    }
+
     fn init_uart(
         &mut self,
         clk: ConfiguredClk<Clk>,
@@ -115,6 +130,26 @@ impl<Uart: UartMap, UartInt: IntToken, Clk: PClkToken> UartDrv<Uart, UartInt, Cl
     }
 }
 
+/// Uart baud rate divider updater.
+pub struct UartClkListener<Uart: UartMap, Clk: PClkToken> {
+    uart: Arc<UartDiverged<Uart>>,
+    clk: PhantomData<Clk>,
+    baud_rate: BaudRate,
+    oversampling: u32,
+}
+
+impl<Uart: UartMap, Clk: PClkToken> UartClkListener<Uart, Clk> {
+    /// Reprogram the baud rate divider for a new uart clock frequency.
+    /// Any ongoing transmission or reception is corrupted, so it should only be called while the uart is idle.
+    pub fn set_clk(&self, clk: ConfiguredClk<Clk>) {
+        self.uart.uart_brr.store_reg(|r, v| {
+            let (div_man, div_frac) = uart_brr(clk, self.baud_rate, self.oversampling);
+            r.div_mantissa().write(v, div_man);
+            r.div_fraction().write(v, div_frac);
+        });
+    }
+}
+
 pub trait IntoRxDrv<
     Uart: UartMap,
     UartInt: IntToken,
@@ -128,7 +163,7 @@ pub trait IntoRxDrv<
         self,
         rx_cfg: DmaChCfg<DmaCh, DmaStCh, DmaInt>,
         rx_pins: &UartPins<Uart, Defined, Tx>,
-    ) -> UartRxDrv<Uart, UartInt, DmaCh>;
+    ) -> UartRxDrv<Uart, UartInt, DmaCh, DmaInt>;
 }
 
 pub trait IntoTxDrv<
@@ -165,7 +200,7 @@ pub trait IntoTrxDrv<
         pins: &UartPins<Uart, Defined, Defined>,
     ) -> (
         UartTxDrv<Uart, UartInt, TxDmaCh, TxDmaInt>,
-        UartRxDrv<Uart, UartInt, RxDmaCh>);
+        UartRxDrv<Uart, UartInt, RxDmaCh, RxDmaInt>);
 }
 
 #[macro_export]
@@ -195,6 +230,7 @@ macro_rules! rx_drv_init {
                 drone_stm32_map::periph::uart::$uart,
                 UartInt,
                 drone_stm32_map::periph::dma::ch::$ch,
+                DmaRxInt,
             > {
                 crate::rx::UartRxDrv::init(self.uart, self.uart_int, rx_cfg)
             }
@@ -276,6 +312,7 @@ macro_rules! trx_drv_init {
                 drone_stm32_map::periph::uart::$uart,
                 UartInt,
                 drone_stm32_map::periph::dma::ch::$rx_ch,
+                RxDmaInt,
             >) {
                 let tx = crate::tx::UartTxDrv::init(self.uart.clone(), self.uart_int, tx_cfg);
                 let rx = crate::rx::UartRxDrv::init(self.uart, self.uart_int, rx_cfg);
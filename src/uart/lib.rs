@@ -12,7 +12,7 @@ mod setup;
 
 extern crate alloc;
 
-pub use self::drv::UartDrv;
+pub use self::drv::{UartClkListener, UartDrv};
 pub use self::prelude::*;
 pub use self::setup::{UartSetup, BaudRate, Parity, StopBits};
 pub use drone_stm32_map::periph::uart::UartMap;
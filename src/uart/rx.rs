@@ -1,4 +1,4 @@
-use crate::diverged::{DmaChDiverged, UartDiverged};
+use crate::diverged::UartDiverged;
 use alloc::sync::Arc;
 use core::ops::Range;
 use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
@@ -6,16 +6,18 @@ use drone_stm32_map::periph::{
     dma::ch::{traits::*, DmaChMap},
     uart::{traits::*, UartMap},
 };
-use drone_stm32f4_dma_drv::{DmaChCfg, DmaStChToken};
+use drone_stm32f4_dma_drv::{
+    DmaChCfg, DmaDataSize, DmaDir, DmaStChToken, DmaStream, DmaStreamSetup,
+};
 
-pub struct UartRxDrv<Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap> {
+pub struct UartRxDrv<Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap, DmaRxInt: IntToken> {
     pub(crate) uart: Arc<UartDiverged<Uart>>,
     pub(crate) uart_int: UartInt,
-    pub(crate) dma: DmaChDiverged<DmaRx>,
+    pub(crate) dma: DmaStream<DmaRx, DmaRxInt>,
 }
 
-pub struct RxGuard<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap> {
-    drv: &'sess UartRxDrv<Uart, UartInt, DmaRx>,
+pub struct RxGuard<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap, DmaRxInt: IntToken> {
+    drv: &'sess UartRxDrv<Uart, UartInt, DmaRx, DmaRxInt>,
     ring_buf: Box<[u8]>,
     first: usize,
     last_read_wrapped: bool,
@@ -26,35 +28,29 @@ pub enum RxError {
     Overflow,
 }
 
-impl<Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
-    UartRxDrv<Uart, UartInt, DmaRx>
+impl<Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap, DmaRxInt: IntToken>
+    UartRxDrv<Uart, UartInt, DmaRx, DmaRxInt>
 {
-    pub(crate) fn init<DmaRxStCh: DmaStChToken, DmaRxInt: IntToken>(
+    pub(crate) fn init<DmaRxStCh: DmaStChToken>(
         uart: Arc<UartDiverged<Uart>>,
         uart_int: UartInt,
         rx_cfg: DmaChCfg<DmaRx, DmaRxStCh, DmaRxInt>,
     ) -> Self {
-        let DmaChCfg {
-            dma_ch,
-            dma_int,
-            dma_pl,
-            ..
-        } = rx_cfg;
-        let rx = Self {
-            uart,
-            uart_int,
-            dma: dma_ch.into(),
+        let setup = DmaStreamSetup {
+            circ: true,
+            tcie: false,
+            ..DmaStreamSetup::new(DmaDir::PeriphToMem, uart.uart_dr.as_mut_ptr() as u32)
         };
-        rx.dma
-            .init_dma_rx(rx.uart.uart_dr.as_mut_ptr() as u32, DmaRxStCh::NUM, dma_pl);
-        rx.dma.panic_on_err(dma_int);
-        rx
+        let dma = DmaStream::init(rx_cfg, setup);
+        // The data register holds at most 9 data bits.
+        assert!(dma.data_size() != DmaDataSize::Word);
+        Self { uart, uart_int, dma }
     }
 
     /// Enable rx operation for the uart peripheral and return a guard that disables the receiver when dropped.
     /// Bytes are received into `ring_buf` and `read()` calls must be made in a sufficent pace to keep up with the reception.
     /// `read()' calls must always keep the ring buffer less than half full for the driver to correctly detect if overflows have occured.
-    pub fn start(&mut self, ring_buf: Box<[u8]>) -> RxGuard<Uart, UartInt, DmaRx> {
+    pub fn start(&mut self, ring_buf: Box<[u8]>) -> RxGuard<Uart, UartInt, DmaRx, DmaRxInt> {
         let mut rx = RxGuard {
             drv: self,
             ring_buf,
@@ -66,11 +62,12 @@ impl<Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
     }
 }
 
-impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
-    RxGuard<'sess, Uart, UartInt, DmaRx>
+impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap, DmaRxInt: IntToken>
+    RxGuard<'sess, Uart, UartInt, DmaRx, DmaRxInt>
 {
     /// Read from the rx ring buffer into `buf`.
     /// Wait for any receiption if no bytes are readily awailable in the ring buffer.
+    /// Only whole data items are read, i.e. pairs of bytes when the dma data size is a half-word.
     pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, RxError> {
         let drv = self.drv;
         // RX Buffer layout:
@@ -93,8 +90,12 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
         // The transfer completed interrupt flag (TCIF) is asserted when this happens,
         // which is used to detect overflows in the ring buffer.
 
-        let mut ndtr = drv.dma.dma_cndtr.ndt().read_bits() as usize;
-        let mut end = self.ring_buf.len() - ndtr;
+        // NDTR counts data items and not bytes when 9-bit frames are received as half-words,
+        // so only whole data items are read into `buf`.
+        let size = drv.dma.data_size().bytes();
+        let buf = &mut buf[..buf.len() / size * size];
+        let mut ndtr = drv.dma.remaining();
+        let mut end = self.ring_buf.len() - ndtr * size;
 
         if self.first == end {
             // There currently no bytes readily available in the buffer.
@@ -102,9 +103,9 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
             // Return a buffer overflow error if TCIF is asserted
             // as the DMA controller in that case has wrapped.
             // This is the special case where n*ring_buf.len(), n > 0,1,2,..., bytes have been written since last read.
-            if drv.dma.dma_isr_tcif.read_bit() {
+            if drv.dma.is_complete() {
                 // Clear transfer completed interrupt flag.
-                drv.dma.dma_ifcr_ctcif.set_bit();
+                drv.dma.clear_complete();
 
                 return Err(RxError::Overflow);
             }
@@ -113,8 +114,8 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
             self.any_rx_activity(ndtr).await;
 
             // Update the ring buffer values to new values after some bytes have been received.
-            ndtr = drv.dma.dma_cndtr.ndt().read_bits() as usize;
-            end = self.ring_buf.len() - ndtr;
+            ndtr = drv.dma.remaining();
+            end = self.ring_buf.len() - ndtr * size;
         }
 
         // There are at this time bytes readily available in the ring buffer.
@@ -124,9 +125,9 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
 
             // Return a buffer overflow error if TCIF is asserted
             // as the DMA controller in that case has wrapped.
-            if drv.dma.dma_isr_tcif.read_bit() {
+            if drv.dma.is_complete() {
                 // Clear transfer completed interrupt flag.
-                drv.dma.dma_ifcr_ctcif.set_bit();
+                drv.dma.clear_complete();
                 return Err(RxError::Overflow);
             }
             self.last_read_wrapped = false;
@@ -144,9 +145,9 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
 
                 // Return a buffer overflow error if TCIF is asserted
                 // as the DMA controller in that case has wrapped.
-                if drv.dma.dma_isr_tcif.read_bit() {
+                if drv.dma.is_complete() {
                     // Clear transfer completed interrupt flag.
-                    drv.dma.dma_ifcr_ctcif.set_bit();
+                    drv.dma.clear_complete();
                     return Err(RxError::Overflow);
                 }
                 self.last_read_wrapped = false;
@@ -160,7 +161,7 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
                 // so the next read will not have any unread tail bytes in the ring buffer.
 
                 // Clear transfer completed interrupt flag.
-                drv.dma.dma_ifcr_ctcif.set_bit();
+                drv.dma.clear_complete();
                 if self.last_read_wrapped {
                     return Err(RxError::PossibleOverflow);
                 }
@@ -193,7 +194,7 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
 
         // 1-2. Configure the dma stream and enable it.
         unsafe {
-            drv.dma.setup_stream(self.ring_buf.as_ref());
+            drv.dma.start_rx(self.ring_buf.as_mut());
         }
 
         // 3a. Configure uart to receive on DMA channel.
@@ -211,15 +212,8 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
     fn stop(&mut self) {
         let drv = self.drv;
 
-        // 1. Disable dma stream.
-        drv.dma.dma_ccr.modify_reg(|r, v| r.en().clear(v));
-
-        // 2. Wait until the EN bit in DMA_SxCR register is reset.
-        loop {
-            if !drv.dma.dma_ccr.en().read_bit() {
-                break;
-            }
-        }
+        // 1-2. Disable dma stream and wait until the EN bit in DMA_SxCR register is reset.
+        drv.dma.stop();
 
         // 3a. Disable receiver.
         drv.uart.uart_cr1.modify_reg(|r, v| {
@@ -234,7 +228,7 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
 
     async fn any_rx_activity(&mut self, old_ndtr: usize) {
         let drv = self.drv;
-        let dma_cndtr = drv.dma.dma_cndtr;
+        let dma_cndtr = drv.dma.ndtr();
         let any_rx = drv.uart_int.add_future(fib::new_fn(move || {
             // Note that we cannot clear the RXNE flag as it is automatically cleared by the DMA controller.
             let new_ndtr = dma_cndtr.ndt().read_bits() as usize;
@@ -250,7 +244,7 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
             r.rxneie().set(v);
         });
 
-        let new_ndtr = drv.dma.dma_cndtr.ndt().read_bits() as usize;
+        let new_ndtr = drv.dma.remaining();
         if new_ndtr == old_ndtr {
             // Wait for actitivy.
             any_rx.await;
@@ -263,7 +257,9 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap>
     }
 }
 
-impl<Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap> Drop for RxGuard<'_, Uart, UartInt, DmaRx> {
+impl<Uart: UartMap, UartInt: IntToken, DmaRx: DmaChMap, DmaRxInt: IntToken> Drop
+    for RxGuard<'_, Uart, UartInt, DmaRx, DmaRxInt>
+{
     /// Stop the receiver.
     fn drop(&mut self) {
         self.stop();
@@ -1,17 +1,18 @@
-use crate::diverged::{DmaChDiverged, UartDiverged};
+use crate::diverged::UartDiverged;
 use alloc::sync::Arc;
 use drone_cortexm::{fib, reg::prelude::*, thr::prelude::*};
 use drone_stm32_map::periph::{
     dma::ch::DmaChMap,
     uart::{traits::*, UartMap},
 };
-use drone_stm32f4_dma_drv::{DmaChCfg, DmaStChToken};
+use drone_stm32f4_dma_drv::{
+    DmaChCfg, DmaDataSize, DmaDir, DmaStChToken, DmaStream, DmaStreamSetup,
+};
 
 pub struct UartTxDrv<Uart: UartMap, UartInt: IntToken, DmaTx: DmaChMap, DmaTxInt: IntToken> {
     pub(crate) uart: Arc<UartDiverged<Uart>>,
     pub(crate) uart_int: UartInt,
-    pub(crate) dma: DmaChDiverged<DmaTx>,
-    pub(crate) dma_int: DmaTxInt,
+    pub(crate) dma: DmaStream<DmaTx, DmaTxInt>,
 }
 
 pub struct TxGuard<'sess, Uart: UartMap, UartInt: IntToken, DmaTx: DmaChMap, DmaTxInt: IntToken> {
@@ -27,22 +28,11 @@ impl<Uart: UartMap, UartInt: IntToken, DmaTx: DmaChMap, DmaTxInt: IntToken>
         uart_int: UartInt,
         tx_cfg: DmaChCfg<DmaTx, DmaTxStCh, DmaTxInt>,
     ) -> Self {
-        let DmaChCfg {
-            dma_ch,
-            dma_int,
-            dma_pl,
-            ..
-        } = tx_cfg;
-        let tx = Self {
-            uart,
-            uart_int,
-            dma: dma_ch.into(),
-            dma_int,
-        };
-        tx.dma
-            .init_dma_tx(tx.uart.uart_dr.as_mut_ptr() as u32, DmaTxStCh::NUM, dma_pl);
-        tx.dma.panic_on_err(dma_int);
-        tx
+        let setup = DmaStreamSetup::new(DmaDir::MemToPeriph, uart.uart_dr.as_mut_ptr() as u32);
+        let dma = DmaStream::init(tx_cfg, setup);
+        // The data register holds at most 9 data bits.
+        assert!(dma.data_size() != DmaDataSize::Word);
+        Self { uart, uart_int, dma }
     }
 
     /// Enable tx operation for the uart peripheral and return a guard that disables the transmitter when dropped.
@@ -90,30 +80,17 @@ impl<'sess, Uart: UartMap, UartInt: IntToken, DmaTx: DmaChMap, DmaTxInt: IntToke
         drv.uart.uart_dr.load_val();
 
         // Setup DMA transfer parameters.
-        drv.dma.setup_stream(buf);
+        drv.dma.start_tx(buf);
 
         // Start listen for DMA transfer to complete.
         // The transfer completes just after the second last byte is being sent on the wire.
-        let dma_isr_tcif = drv.dma.dma_isr_tcif;
-        let dma_ifcr_ctcif = drv.dma.dma_ifcr_ctcif;
-        let dma_tc = drv.dma_int.add_future(fib::new_fn(move || {
-            if dma_isr_tcif.read_bit() {
-                // Clear transfer completed interrupt flag.
-                dma_ifcr_ctcif.set_bit();
-                fib::Complete(())
-            } else {
-                fib::Yielded(())
-            }
-        }));
+        let dma_tc = drv.dma.transfer_complete();
 
         // The uart transmission complete flag (TC) is cleared
         // by the sequence: Read status register (SR) and write data register (DR).
         // We read the status register here, and the dma writes the DR.
         drv.uart.uart_sr.load_val();
 
-        // Clear any outstanding fifo error interrupt flag by settings its clear register.
-        drv.dma.dma_ifcr_cfeif.set_bit();
-
         // Start transfer on DMA channel.
         drv.uart.uart_cr3.modify_reg(|r, v| {
             r.dmat().set(v);